@@ -0,0 +1,92 @@
+use std::num::NonZeroUsize;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use phonet::{BatchOptions, Phonet};
+
+/// Words to validate, made of syllables of consonant, vowel, and optional `n`
+///
+/// Some words are invalid, such as words with `ti` or `nn`
+fn lexicon(count: usize) -> Vec<String> {
+    const CONSONANTS: &[char] = &['p', 't', 'k', 'm', 'n', 's', 'w', 'l', 'j'];
+    const VOWELS: &[char] = &['a', 'e', 'i', 'o', 'u'];
+
+    // Simple random number generator, so words are the same each run
+    let mut state: u64 = 1;
+    let mut random = |max: usize| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize % max
+    };
+
+    (0..count)
+        .map(|_| {
+            let mut word = String::new();
+            for _ in 0..random(3) + 1 {
+                word.push(CONSONANTS[random(CONSONANTS.len())]);
+                word.push(VOWELS[random(VOWELS.len())]);
+                if random(4) == 0 {
+                    word.push('n');
+                }
+            }
+            word
+        })
+        .collect()
+}
+
+fn validate(c: &mut Criterion) {
+    let scheme = Phonet::parse(include_str!("../examples/tokipona.phonet"))
+        .expect("Failed to parse example");
+    let words = lexicon(100_000);
+
+    let mut group = c.benchmark_group("validate_all");
+    group.throughput(Throughput::Elements(words.len() as u64));
+    group.sample_size(10);
+
+    // Each rule matched one at a time, without combining rules
+    group.bench_function("each_rule", |b| {
+        b.iter(|| {
+            words
+                .iter()
+                .map(|word| {
+                    scheme.rules.iter().position(|rule| {
+                        rule.intent != rule.pattern.is_match(word).unwrap_or_default()
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+
+    // Sequential, as `Results::run` does
+    group.bench_function("first_violation", |b| {
+        b.iter(|| {
+            words
+                .iter()
+                .map(|word| scheme.first_violation(word))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    let cores = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut threads: Vec<_> = [1, 2, 4, 8]
+        .into_iter()
+        .filter(|threads| *threads < cores)
+        .collect();
+    threads.push(cores);
+
+    for threads in threads {
+        let options = BatchOptions {
+            threads: NonZeroUsize::new(threads),
+        };
+        group.bench_with_input(
+            BenchmarkId::new("threads", threads),
+            &options,
+            |b, options| b.iter(|| scheme.validate_all_with(&words, options)),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, validate);
+criterion_main!(benches);
@@ -1,8 +1,13 @@
 // * This file is just for the binary
 
-use clap::{builder::PossibleValue, Parser, ValueEnum};
+use std::{num::NonZeroUsize, ops::Range};
 
-use phonet::DisplayLevel::{self, *};
+use clap::{builder::PossibleValue, Parser, Subcommand, ValueEnum};
+
+use phonet::{
+    DisplayLevel::{self, *},
+    OutputFormat,
+};
 
 #[derive(Parser)]
 #[clap(author, version)]
@@ -10,15 +15,27 @@ use phonet::DisplayLevel::{self, *};
 ///
 /// More information: https://github.com/darccyy/phonet
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Custom test, separate with comma (Ignores tests in file)
     #[arg(short, long)]
     pub tests: Option<String>,
 
     /// Name and path of file to run and test
     ///
-    /// Eg. `phonet -f ./myfile.phonet`
-    #[arg(short, long, default_value_t = String::from("phonet"))]
-    pub file: String,
+    /// Can be given multiple times. Directories are searched for `.phonet` files, and glob patterns
+    /// are expanded. Results of each file are followed by a summary of every file
+    ///
+    /// Eg. `phonet -f ./myfile.phonet` or `phonet -f ./dialects -f "tests/**/*.phonet"`
+    #[arg(
+        short,
+        long = "file",
+        value_name = "FILE",
+        default_value = "phonet",
+        global = true
+    )]
+    pub files: Vec<String>,
 
     /// What types of outputs to display
     ///
@@ -28,7 +45,33 @@ pub struct Args {
     #[arg(short, long, default_value_t = ShowAll, value_enum)]
     pub display_level: DisplayLevel,
 
+    /// Format of test results
+    ///
+    /// Machine-readable formats ignore `--display-level` and color
+    ///
+    /// With machine-readable formats, generated, listed, and counted words are written to standard error
+    ///
+    /// Cannot be used with a subcommand
+    ///
+    /// Eg. `phonet --format json`
+    #[arg(long, default_value = "human", value_enum)]
+    pub format: OutputFormat,
+
+    /// Exit with failure status if no tests ran
+    #[arg(long)]
+    pub fail_on_empty: bool,
+
+    /// Display every rule that a failed test does not follow, not just the first
+    #[arg(short, long)]
+    pub all_violations: bool,
+
+    /// Display tests rejected by each rule, and list rules which reject no test or no negative test
+    #[arg(long)]
+    pub coverage: bool,
+
     /// Minify file and save
+    ///
+    /// Statements are kept in order, with reasons. Notes are only kept with tests
     #[arg(short, long, value_enum)]
     pub minify: Option<Option<WithTests>>,
 
@@ -52,13 +95,121 @@ pub struct Args {
     #[arg(long = "gmax")]
     pub generate_max_len: Option<usize>,
 
+    /// Generate words from syllable templates, with a range of syllable counts
+    ///
+    /// Use with the `--generate` or `-g` flag. Replaces `--gmin` and `--gmax`
+    ///
+    /// Range end is exclusive with `..`, and inclusive with `..=`, as in Rust
+    ///
+    /// Eg. `--syllables 2` for 2 syllables, `--syllables 2..=4` for 2 to 4, or `--syllables 2..4` for 2 or 3
+    #[arg(long, value_parser = parse_range)]
+    pub syllables: Option<Range<usize>>,
+
+    /// Set maximum amount of candidate words to try for each word, before stopping generation
+    ///
+    /// Use with the `--generate` or `-g` flag
+    ///
+    /// Use 0 for no limit [default: 100000]
+    #[arg(long = "max-attempts")]
+    pub generate_max_attempts: Option<usize>,
+
+    /// Set maximum time to spend generating words, in seconds
+    ///
+    /// Use with the `--generate` or `-g` flag
+    #[arg(long = "timeout")]
+    pub generate_timeout: Option<f64>,
+
+    /// Set seed for generating words, to generate the same words each time
+    ///
+    /// Use with the `--generate` or `-g` flag
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// List every valid word, up to a maximum length
+    ///
+    /// Words are listed in alphabetical order, followed by the amount of words of each length
+    ///
+    /// Note: This can take a long time for large maximum lengths
+    #[arg(short, long, value_name = "MAX_LEN")]
+    pub enumerate: Option<usize>,
+
+    /// Count valid words of each length in range
+    ///
+    /// Small lengths are counted exactly, large lengths are estimated by sampling
+    ///
+    /// Words are built from the segments of the 'any' class. Syllable templates are not used
+    ///
+    /// Range end is exclusive with `..`, and inclusive with `..=`, as in Rust
+    ///
+    /// Eg. `--capacity 1..=8` for lengths 1 to 8
+    #[arg(long, value_parser = parse_range)]
+    pub capacity: Option<Range<usize>>,
+
     /// Display output in default color
     ///
     /// Use for piping standard output to a file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub no_color: bool,
 }
 
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check file for possible mistakes, without running tests
+    ///
+    /// Warns about unused classes and reasons, duplicate rules and tests, contradicting tests, and
+    /// classes with letters not in the 'any' class
+    ///
+    /// Allow warnings for a file with a comment, such as `#! allow unused-class W001`
+    Lint,
+
+    /// Format file in canonical layout, and save
+    ///
+    /// Puts mode and classes at the top of the file, indents rules and tests after reasons and
+    /// notes, normalizes spacing, and aligns comments after statements
+    Fmt {
+        /// Check that file is formatted, without saving
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Check whether each word of a word list is valid, without running tests
+    ///
+    /// Words are read one per line, or from a column of a TSV or CSV file. Invalid words are shown
+    /// with the reason of the first rule they do not follow
+    ///
+    /// Eg. `phonet words lexicon.csv --column 2 --header --split ./checked`
+    Words {
+        /// File to read words from
+        ///
+        /// Reads from standard input if not given, or `-`
+        input: Option<String>,
+
+        /// Column of words, starting at 1, for a TSV or CSV file
+        ///
+        /// Columns are separated by tabs if a line has a tab, otherwise by commas
+        #[arg(long)]
+        column: Option<usize>,
+
+        /// Skip first line of word list, such as a header row
+        #[arg(long)]
+        header: bool,
+
+        /// Save valid words to `valid.txt`, and invalid words with reasons to `invalid.txt`, in
+        /// directory
+        ///
+        /// With multiple files, the name of each file is added before, such as
+        /// `tokipona.valid.txt`
+        #[arg(long, value_name = "DIR")]
+        split: Option<String>,
+
+        /// Amount of threads to check words with
+        ///
+        /// Uses every available core if not given
+        #[arg(long)]
+        threads: Option<NonZeroUsize>,
+    },
+}
+
 #[derive(Clone, Copy, Debug)]
 /// Custom implementation of boolean, for argument aliases
 pub enum WithTests {
@@ -79,3 +230,56 @@ impl ValueEnum for WithTests {
         &[Self::Tests]
     }
 }
+
+/// Parse range from string
+///
+/// Accepts a single number (`2`), exclusive range (`2..4`), or inclusive range (`2..=4`)
+fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let parse_number = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("`{s}` is not a valid number"))
+    };
+    // Exclusive end after inclusive end
+    let after = |end: usize| {
+        end.checked_add(1)
+            .ok_or_else(|| format!("`{end}` is too large for end of range"))
+    };
+
+    let range = if let Some((start, end)) = s.split_once("..=") {
+        parse_number(start)?..after(parse_number(end)?)?
+    } else if let Some((start, end)) = s.split_once("..") {
+        parse_number(start)?..parse_number(end)?
+    } else {
+        let number = parse_number(s)?;
+        number..after(number)?
+    };
+
+    if range.is_empty() {
+        return Err(format!("Range `{s}` is empty"));
+    }
+
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_works() {
+        assert_eq!(parse_range("2"), Ok(2..3));
+        assert_eq!(parse_range("2..4"), Ok(2..4));
+        assert_eq!(parse_range("2..=4"), Ok(2..5));
+        assert!(parse_range("4..2").is_err());
+        assert!(parse_range("2..2").is_err());
+        assert!(parse_range("a..4").is_err());
+        assert!(parse_range("").is_err());
+        assert!(parse_range(&usize::MAX.to_string()).is_err());
+        assert!(parse_range(&format!("2..={}", usize::MAX)).is_err());
+        assert_eq!(
+            parse_range(&format!("2..{}", usize::MAX)),
+            Ok(2..usize::MAX)
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    num::NonZeroUsize,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{Phonet, Violation};
+
+/// Options for validating many words
+///
+/// Use with `Phonet::validate_all_with`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BatchOptions {
+    /// Amount of threads to validate words with
+    ///
+    /// Uses the amount of available cores if `None`
+    pub threads: Option<NonZeroUsize>,
+}
+
+/// Validity of many words
+///
+/// Create with `Phonet::validate_all`
+#[derive(Debug, Serialize)]
+pub struct Batch {
+    /// First rule that each word does not follow, or `None` if word is valid
+    ///
+    /// In the same order as the words that were validated
+    pub list: Vec<Option<Violation>>,
+    /// Time taken to validate every word
+    pub elapsed: Duration,
+    /// Amount of threads which words were validated with
+    pub threads: usize,
+}
+
+impl Batch {
+    /// Get amount of valid words
+    pub fn valid_count(&self) -> usize {
+        self.list.iter().filter(|item| item.is_none()).count()
+    }
+
+    /// Get amount of invalid words
+    pub fn invalid_count(&self) -> usize {
+        self.list.len() - self.valid_count()
+    }
+
+    /// Get amount of words validated each second
+    pub fn words_per_second(&self) -> f64 {
+        self.list.len() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl Phonet {
+    /// Validate many words, using every available core
+    ///
+    /// Each word gives the same result as `Phonet::first_violation`
+    pub fn validate_all<T>(&self, words: &[T]) -> Batch
+    where
+        T: AsRef<str> + Sync,
+    {
+        self.validate_all_with(words, &BatchOptions::default())
+    }
+
+    /// Validate many words, with options
+    ///
+    /// Words are split into one chunk for each thread, with sizes differing by at most one word.
+    /// Results are kept in order of words
+    pub fn validate_all_with<T>(&self, words: &[T], options: &BatchOptions) -> Batch
+    where
+        T: AsRef<str> + Sync,
+    {
+        let start = Instant::now();
+
+        let threads = options
+            .threads
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+            // No empty chunks
+            .clamp(1, words.len().max(1));
+
+        let validate = |chunk: &[T]| -> Vec<Option<Violation>> {
+            chunk
+                .iter()
+                .map(|word| self.first_violation(word.as_ref()))
+                .collect()
+        };
+
+        let list = if threads == 1 {
+            validate(words)
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<_> = split_evenly(words, threads)
+                    .into_iter()
+                    .map(|chunk| scope.spawn(move || validate(chunk)))
+                    .collect();
+
+                // Join in order of chunks, to keep order of words
+                handles
+                    .into_iter()
+                    .flat_map(|handle| {
+                        handle
+                            .join()
+                            .expect("Failed to validate words. This error should NEVER APPEAR!")
+                    })
+                    .collect()
+            })
+        };
+
+        Batch {
+            list,
+            elapsed: start.elapsed(),
+            threads,
+        }
+    }
+}
+
+/// Split list into amount of chunks, with sizes differing by at most one item
+///
+/// First chunks have one extra item, if list does not split evenly
+fn split_evenly<T>(items: &[T], count: usize) -> Vec<&[T]> {
+    let (size, extra) = (items.len() / count, items.len() % count);
+
+    let mut rest = items;
+    (0..count)
+        .map(|index| {
+            let (chunk, tail) = rest.split_at(size + usize::from(index < extra));
+            rest = tail;
+            chunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_all_keeps_order() {
+        let scheme = Phonet::parse("$_ = [ab]; @ Only a or b; + ^ <_>+ $; @ No double; ! (.)\\1")
+            .expect("Failed to parse");
+
+        let words: Vec<_> = (0..1000)
+            .map(|i| ["ab", "aa", "ac", "ba"][i % 4].to_string())
+            .collect();
+
+        let expected: Vec<_> = words
+            .iter()
+            .map(|word| scheme.first_violation(word))
+            .collect();
+
+        for threads in [1, 3, 8, 2000] {
+            let batch = scheme.validate_all_with(
+                &words,
+                &BatchOptions {
+                    threads: NonZeroUsize::new(threads),
+                },
+            );
+
+            assert_eq!(batch.list, expected);
+            assert_eq!(batch.threads, threads.min(words.len()));
+            assert_eq!(batch.valid_count(), 500);
+            assert_eq!(batch.invalid_count(), 500);
+        }
+
+        // Every thread has words, even if words do not split evenly
+        for (count, threads) in [(10, 8), (7, 3), (9, 9)] {
+            let batch = scheme.validate_all_with(
+                &words[..count],
+                &BatchOptions {
+                    threads: NonZeroUsize::new(threads),
+                },
+            );
+            assert_eq!(batch.list, expected[..count]);
+            assert_eq!(batch.threads, threads);
+        }
+
+        let batch = scheme.validate_all::<&str>(&[]);
+        assert!(batch.list.is_empty());
+        assert_eq!(batch.threads, 1);
+    }
+
+    #[test]
+    fn split_evenly_works() {
+        let items: Vec<_> = (0..10).collect();
+
+        let sizes = |count| -> Vec<_> {
+            split_evenly(&items, count)
+                .iter()
+                .map(|chunk| chunk.len())
+                .collect()
+        };
+        assert_eq!(sizes(8), vec![2, 2, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(sizes(3), vec![4, 3, 3]);
+        assert_eq!(sizes(10), vec![1; 10]);
+
+        assert_eq!(split_evenly(&items, 8).concat(), items);
+    }
+}
@@ -0,0 +1,160 @@
+use serde::Serialize;
+
+use crate::{run::location, Phonet};
+
+/// Amount of tests that each rule rejected
+///
+/// Create by running with `RunOptions::coverage`
+#[derive(Debug, Serialize)]
+pub struct Coverage {
+    /// Coverage of each rule, in order of definition
+    pub rules: Vec<RuleCoverage>,
+}
+
+/// Amount of tests that a rule rejected
+#[derive(Debug, Serialize)]
+pub struct RuleCoverage {
+    /// Intent of rule
+    pub intent: bool,
+    /// Line number of rule definition
+    pub line: usize,
+    /// Path of file which rule was defined in, if rule was included from another file
+    pub file: Option<String>,
+    /// Reason of rule, if given
+    pub reason: Option<String>,
+    /// Amount of tests which did not follow rule
+    pub rejected: usize,
+    /// Amount of negative tests (`?!`) which did not follow rule
+    pub rejected_negative: usize,
+    /// Amount of tests where rule was the first rule not followed
+    ///
+    /// This is the rule that gives the reason for the test being invalid
+    pub deciding: usize,
+}
+
+impl Coverage {
+    /// Create coverage for every rule in scheme, with no tests recorded
+    pub(crate) fn new(scheme: &Phonet) -> Self {
+        Self {
+            rules: scheme
+                .rules
+                .iter()
+                .map(|rule| RuleCoverage {
+                    intent: rule.intent,
+                    line: rule.line,
+                    file: rule.file.clone(),
+                    reason: rule
+                        .reason_ref
+                        .and_then(|reason| scheme.reasons.get(reason))
+                        .cloned(),
+                    rejected: 0,
+                    rejected_negative: 0,
+                    deciding: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Record which rules a test does not follow
+    pub(crate) fn record(&mut self, scheme: &Phonet, word: &str, intent: bool) {
+        for (i, index) in scheme.all_failing_rules(word).into_iter().enumerate() {
+            let rule = &mut self.rules[index];
+
+            rule.rejected += 1;
+            if !intent {
+                rule.rejected_negative += 1;
+            }
+            // First rule not followed
+            if i == 0 {
+                rule.deciding += 1;
+            }
+        }
+    }
+
+    /// Get rules which do not reject any negative test (`?!`)
+    pub fn uncovered(&self) -> impl Iterator<Item = &RuleCoverage> {
+        self.rules.iter().filter(|rule| rule.rejected_negative == 0)
+    }
+
+    /// Get rules which do not reject any test
+    pub fn never_rejecting(&self) -> impl Iterator<Item = &RuleCoverage> {
+        self.rules.iter().filter(|rule| rule.rejected == 0)
+    }
+
+    /// Display coverage report to standard output
+    ///
+    /// Shows a table of how many tests each rule rejected, then lists rules which never reject any
+    /// test, and rules which reject tests but no negative test
+    ///
+    /// This can be implemented manually
+    pub fn display(&self, no_color: bool) {
+        let covered = self.rules.len() - self.uncovered().count();
+
+        if no_color {
+            println!(
+                "Rule coverage: {covered} of {total} rules have a negative test",
+                total = self.rules.len()
+            );
+            println!("Line  Rejected  Deciding  Reason");
+        } else {
+            println!(
+                "\x1b[34;1mRule coverage:\x1b[0m {covered} of {total} rules have a negative test",
+                total = self.rules.len()
+            );
+            println!("\x1b[34mLine  Rejected  Deciding  Reason\x1b[0m");
+        }
+
+        // Table of every rule
+        // Rules of included files show file after reason
+        for rule in &self.rules {
+            let file = match &rule.file {
+                Some(file) => format!(" (in `{file}`)"),
+                None => String::new(),
+            };
+
+            println!(
+                "{:>4}  {:>8}  {:>8}  {}{file}",
+                rule.line,
+                rule.rejected,
+                rule.deciding,
+                rule.reason.as_deref().unwrap_or("-"),
+            );
+        }
+
+        let never_rejecting: Vec<_> = self.never_rejecting().collect();
+        display_list("Rules which never reject any test:", &never_rejecting, no_color);
+
+        // Rules which never reject any test are not listed twice
+        let uncovered: Vec<_> = self.uncovered().filter(|rule| rule.rejected > 0).collect();
+        display_list("Rules without a negative test:", &uncovered, no_color);
+    }
+}
+
+/// Display list of rules with title, if not empty
+fn display_list(title: &str, rules: &[&RuleCoverage], no_color: bool) {
+    if rules.is_empty() {
+        return;
+    }
+
+    if no_color {
+        println!("{title}");
+    } else {
+        println!("\x1b[33m{title}\x1b[0m");
+    }
+
+    for rule in rules {
+        if no_color {
+            println!(
+                " - {location}: {reason}",
+                location = location(rule.line, rule.file.as_deref()),
+                reason = rule.reason.as_deref().unwrap_or("No reason given"),
+            );
+        } else {
+            println!(
+                " \x1b[33m-\x1b[0m {location}: \x1b[3m{reason}\x1b[0m",
+                location = location(rule.line, rule.file.as_deref()),
+                reason = rule.reason.as_deref().unwrap_or("No reason given"),
+            );
+        }
+    }
+}
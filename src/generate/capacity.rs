@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{types::Error, Phonet};
+
+/// Options for counting valid words
+///
+/// Use with `Phonet::capacity`
+#[derive(Clone, Debug)]
+pub struct CapacityOptions {
+    /// Maximum amount of candidate words of a length, to count exactly
+    ///
+    /// Lengths with more candidates are estimated by sampling
+    pub exact_limit: f64,
+    /// Amount of random candidates to check, for each estimated length
+    pub samples: usize,
+    /// Seed for random number generator, for estimated lengths
+    ///
+    /// Use `None` for a random seed
+    pub seed: Option<u64>,
+}
+
+impl Default for CapacityOptions {
+    fn default() -> Self {
+        Self {
+            exact_limit: 100_000.0,
+            samples: 10_000,
+            seed: None,
+        }
+    }
+}
+
+/// Amount of valid words of a length
+#[derive(Debug)]
+pub struct Capacity {
+    /// Length of words, in characters
+    pub length: usize,
+    /// Amount of candidate words of this length, made of segments of the 'any' class
+    ///
+    /// This counts sequences of segments, so words which can be split into segments in multiple ways
+    /// (such as with `s`, `h` and `sh`) are counted more than once
+    pub candidates: f64,
+    /// Amount of valid words
+    pub count: CapacityCount,
+}
+
+/// Exact or estimated amount of valid words
+#[derive(Debug)]
+pub enum CapacityCount {
+    /// Every candidate was checked
+    Exact(u64),
+    /// Random candidates were checked
+    Estimate {
+        /// Estimated amount of valid words
+        count: f64,
+        /// Lower bound of 95% confidence interval
+        low: f64,
+        /// Upper bound of 95% confidence interval
+        high: f64,
+        /// Amount of candidates checked
+        samples: usize,
+        /// Amount of checked candidates which were valid
+        valid: usize,
+    },
+}
+
+impl Phonet {
+    /// Count valid words of each length in range, in characters
+    ///
+    /// Lengths with few candidates are counted exactly, using `Phonet::enumerate`. Other lengths are
+    /// estimated by checking random candidates, with a 95% confidence interval
+    ///
+    /// Both count distinct words, even if segments overlap (such as `s`, `h` and `sh`)
+    ///
+    /// Words are built from the segments of the 'any' class (`$_`). Syllable templates are not used,
+    /// so words which cannot be built from templates are still counted
+    pub fn capacity(
+        &self,
+        length: Range<usize>,
+        options: &CapacityOptions,
+    ) -> Result<Vec<Capacity>, Error> {
+        let segments: Vec<String> = self.inventory("_")?;
+        let lengths: Vec<usize> = segments.iter().map(|x| x.chars().count()).collect();
+
+        // Amount of sequences of segments, for every length up to maximum
+        let mut candidates = vec![1.0];
+        for total in 1..length.end {
+            let count = lengths
+                .iter()
+                .filter(|len| **len <= total)
+                .map(|len| candidates[total - len])
+                .sum();
+            candidates.push(count);
+        }
+
+        // Count small lengths exactly, in a single walk
+        let exact_max = length
+            .clone()
+            .filter(|len| candidates[*len] <= options.exact_limit)
+            .max();
+        let mut exact = vec![0; length.end];
+        if let Some(max_len) = exact_max {
+            for word in self.enumerate(max_len)? {
+                exact[word.chars().count()] += 1;
+            }
+        }
+
+        let mut rng = match options.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+
+        let mut capacities = Vec::new();
+        for len in length {
+            let count = if candidates[len] <= options.exact_limit {
+                CapacityCount::Exact(exact[len])
+            } else {
+                // Check random candidates
+                // A word which can be split into segments in multiple ways is sampled once for each
+                // way, so it is counted as a fraction of a word each time
+                let mut valid = 0;
+                let mut distinct = 0.0;
+                for _ in 0..options.samples {
+                    let word = random_sequence(&segments, &lengths, &candidates, len, &mut rng);
+                    if self.first_failing_rule(&word).is_none() {
+                        valid += 1;
+                        distinct += 1.0 / segmentations(&word, &segments);
+                    }
+                }
+
+                let (low, high) = wilson_interval(distinct, options.samples);
+                CapacityCount::Estimate {
+                    count: distinct / options.samples as f64 * candidates[len],
+                    low: low * candidates[len],
+                    high: high * candidates[len],
+                    samples: options.samples,
+                    valid,
+                }
+            };
+
+            capacities.push(Capacity {
+                length: len,
+                candidates: candidates[len],
+                count,
+            });
+        }
+
+        Ok(capacities)
+    }
+}
+
+/// Create random sequence of segments with given length, with every sequence equally likely
+///
+/// `candidates` holds the amount of sequences of each length
+fn random_sequence(
+    segments: &[String],
+    lengths: &[usize],
+    candidates: &[f64],
+    length: usize,
+    rng: &mut impl Rng,
+) -> String {
+    let mut word = String::new();
+    let mut remaining = length;
+
+    while remaining > 0 {
+        // Choose segment, weighted by amount of sequences which can follow it
+        let mut target = rng.gen::<f64>() * candidates[remaining];
+        let mut chosen = None;
+        for (index, len) in lengths.iter().enumerate() {
+            if *len > remaining {
+                continue;
+            }
+            chosen = Some(index);
+            target -= candidates[remaining - len];
+            if target < 0.0 {
+                break;
+            }
+        }
+
+        // Some segment always fits, as length has candidates
+        let index = chosen.expect("No segment fits in remaining length");
+        word.push_str(&segments[index]);
+        remaining -= lengths[index];
+    }
+
+    word
+}
+
+/// Get amount of ways that word can be split into segments
+fn segmentations(word: &str, segments: &[String]) -> f64 {
+    // Amount of ways that prefix of word, by length in bytes, can be split into segments
+    let mut ways = vec![0.0; word.len() + 1];
+    ways[0] = 1.0;
+
+    for start in 0..word.len() {
+        if ways[start] == 0.0 {
+            continue;
+        }
+        for segment in segments {
+            if word[start..].starts_with(segment.as_str()) {
+                ways[start + segment.len()] += ways[start];
+            }
+        }
+    }
+
+    ways[word.len()]
+}
+
+/// Get 95% Wilson score interval of proportion, as lower and upper bounds
+///
+/// `successes` may be fractional, for a mean of values between 0 and 1
+fn wilson_interval(successes: f64, total: usize) -> (f64, f64) {
+    if total == 0 {
+        return (0.0, 1.0);
+    }
+
+    const Z: f64 = 1.96;
+    let n = total as f64;
+    let p = successes / n;
+
+    let center = p + Z * Z / (2.0 * n);
+    let margin = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+    let denominator = 1.0 + Z * Z / n;
+
+    (
+        ((center - margin) / denominator).max(0.0),
+        ((center + margin) / denominator).min(1.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wilson_interval_works() {
+        let (low, high) = wilson_interval(50.0, 100);
+        assert!(low < 0.5 && high > 0.5);
+        assert!((low - 0.404).abs() < 0.001 && (high - 0.596).abs() < 0.001);
+
+        assert!(wilson_interval(0.0, 100).0 < 1e-9);
+        assert!(wilson_interval(100.0, 100).1 > 1.0 - 1e-9);
+    }
+
+    #[test]
+    fn segmentations_works() {
+        let segments: Vec<_> = ["s", "h", "sh", "a"].map(String::from).into();
+
+        assert_eq!(segmentations("sa", &segments), 1.0);
+        assert_eq!(segmentations("sha", &segments), 2.0);
+        assert_eq!(segmentations("shsh", &segments), 4.0);
+        assert_eq!(segmentations("x", &segments), 0.0);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    types::{Error, Rule},
+    Phonet,
+};
+
+/// Iterator over every valid word, up to a maximum length
+///
+/// Create with `Phonet::enumerate`
+///
+/// Words are yielded in lexicographic order (by character), without duplicates
+pub struct Enumerate<'a> {
+    /// Scheme, to validate words
+    scheme: &'a Phonet,
+    /// Rules which, if a prefix does not follow them, no longer words can follow them either
+    prunable: Vec<&'a Rule>,
+    /// Segments of 'any' class, as characters
+    segments: Vec<Vec<char>>,
+    /// Maximum length of words, in characters
+    max_len: usize,
+    /// Prefixes left to walk, with length in characters and position in segments
+    stack: Vec<(String, usize, State)>,
+}
+
+/// Position of a prefix, within the segments it is built from
+///
+/// A prefix can have multiple positions, if segments overlap (such as `s`, `h` and `sh`)
+#[derive(Clone)]
+struct State {
+    /// Whether prefix ends between segments
+    boundary: bool,
+    /// Index of segment and amount of characters consumed, for each unfinished segment
+    partial: Vec<(usize, usize)>,
+}
+
+impl Phonet {
+    /// Get iterator over every valid word, up to a maximum length in characters
+    ///
+    /// Words are built from the segments of the 'any' class (`$_`), walked depth-first in
+    /// lexicographic order
+    ///
+    /// Prefixes which break a negative rule are skipped, if that rule does not depend on the end of the
+    /// word (such as with `$` or lookaheads)
+    pub fn enumerate(&self, max_len: usize) -> Result<Enumerate<'_>, Error> {
+        let segments = self
+            .inventory("_")?
+            .iter()
+            .map(|segment| segment.chars().collect())
+            .collect();
+
+        let prunable = self.rules.iter().filter(|rule| is_prunable(rule)).collect();
+
+        Ok(Enumerate {
+            scheme: self,
+            prunable,
+            segments,
+            max_len,
+            stack: vec![(
+                String::new(),
+                0,
+                State {
+                    boundary: true,
+                    partial: Vec::new(),
+                },
+            )],
+        })
+    }
+}
+
+impl<'a> Iterator for Enumerate<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((word, len, state)) = self.stack.pop() {
+            // Skip prefix, and every word starting with it
+            if !word.is_empty()
+                && self.prunable.iter().any(|rule| {
+                    rule.pattern
+                        .is_match(&word)
+                        .expect("Failed checking regex match. This error should NEVER APPEAR!")
+                })
+            {
+                continue;
+            }
+
+            // Add longer prefixes, in reverse order, so that they are walked in order
+            if len < self.max_len {
+                for ch in self.next_chars(&state).into_iter().rev() {
+                    let next = self.step(&state, ch);
+                    self.stack.push((format!("{word}{ch}"), len + 1, next));
+                }
+            }
+
+            // Word must be made of whole segments
+            if state.boundary
+                && !word.is_empty()
+                && self.scheme.first_failing_rule(&word).is_none()
+            {
+                return Some(word);
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> Enumerate<'a> {
+    /// Get characters which can follow a prefix, sorted
+    fn next_chars(&self, state: &State) -> Vec<char> {
+        let mut chars: Vec<char> = state
+            .partial
+            .iter()
+            .map(|&(index, consumed)| self.segments[index][consumed])
+            .collect();
+
+        if state.boundary {
+            chars.extend(self.segments.iter().map(|segment| segment[0]));
+        }
+
+        chars.sort();
+        chars.dedup();
+        chars
+    }
+
+    /// Get position of prefix, after adding a character
+    fn step(&self, state: &State, ch: char) -> State {
+        let mut next = State {
+            boundary: false,
+            partial: Vec::new(),
+        };
+
+        // Continue unfinished segments, and start new segments if between segments
+        let starts = state
+            .boundary
+            .then(|| (0..self.segments.len()).map(|index| (index, 0)))
+            .into_iter()
+            .flatten();
+
+        for (index, consumed) in state.partial.iter().copied().chain(starts) {
+            let segment = &self.segments[index];
+            if segment[consumed] != ch {
+                continue;
+            }
+
+            if consumed + 1 == segment.len() {
+                next.boundary = true;
+            } else {
+                next.partial.push((index, consumed + 1));
+            }
+        }
+
+        next.partial.sort();
+        next.partial.dedup();
+        next
+    }
+}
+
+/// Returns `true` if rule is negative, and any match in a prefix is also a match in longer words
+///
+/// Anchors and assertions which depend on the end of the word are not prunable
+fn is_prunable(rule: &Rule) -> bool {
+    let pattern = rule.pattern.as_str();
+
+    !rule.intent
+        && !["$", r"\b", r"\B", r"\z", r"\Z", "(?=", "(?!"]
+            .iter()
+            .any(|token| pattern.contains(token))
+}
@@ -0,0 +1,331 @@
+use std::{iter::Peekable, str::Chars};
+
+/// Maximum amount of segments that a class may expand to
+///
+/// Prevents patterns such as `[a-z]{5}` from creating millions of segments
+const MAX_SEGMENTS: usize = 10_000;
+
+/// Expand a class value (with classes already substituted) into a list of segments
+///
+/// A segment is a string that the class can match, such as a single letter, or a digraph like `sh`
+///
+/// Only finite patterns are supported: literals, character sets (`[...]`), groups, alternations (`|`),
+/// and the quantifiers `?`, `{n}` and `{n,m}`
+///
+/// Returned segments are in order of definition, without duplicates or empty strings
+pub fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    let mut chars = pattern.chars().peekable();
+
+    let set = parse_alternation(&mut chars)?;
+
+    // Alternation only stops early at an unmatched closing bracket
+    if let Some(ch) = chars.next() {
+        return Err(format!("Unexpected character `{ch}`"));
+    }
+
+    let mut segments: Vec<String> = Vec::new();
+    for segment in set {
+        if !segment.is_empty() && !segments.contains(&segment) {
+            segments.push(segment);
+        }
+    }
+
+    if segments.is_empty() {
+        return Err("Class does not contain any segments".to_string());
+    }
+
+    Ok(segments)
+}
+
+/// Parse alternatives separated by `|`, until end of pattern or closing round bracket
+fn parse_alternation(chars: &mut Peekable<Chars>) -> Result<Vec<String>, String> {
+    let mut set = parse_sequence(chars)?;
+
+    while chars.peek() == Some(&'|') {
+        chars.next();
+        set.extend(parse_sequence(chars)?);
+        check_size(&set)?;
+    }
+
+    Ok(set)
+}
+
+/// Parse sequence of atoms, each with an optional quantifier
+fn parse_sequence(chars: &mut Peekable<Chars>) -> Result<Vec<String>, String> {
+    // Sequence starts as a single empty string
+    let mut set = vec![String::new()];
+
+    while let Some(&ch) = chars.peek() {
+        if ch == '|' || ch == ')' {
+            break;
+        }
+
+        let atom = parse_atom(chars)?;
+        let atom = parse_quantifier(chars, atom)?;
+
+        set = product(&set, &atom);
+        check_size(&set)?;
+    }
+
+    Ok(set)
+}
+
+/// Parse a single literal, character set, or group
+fn parse_atom(chars: &mut Peekable<Chars>) -> Result<Vec<String>, String> {
+    let Some(ch) = chars.next() else {
+        return Err("Unexpected end of pattern".to_string());
+    };
+
+    Ok(match ch {
+        // Group
+        '(' => {
+            // Only non-capturing groups may have flags
+            if chars.peek() == Some(&'?') {
+                chars.next();
+                if chars.next() != Some(':') {
+                    return Err("Only plain and non-capturing groups are supported".to_string());
+                }
+            }
+
+            let set = parse_alternation(chars)?;
+
+            if chars.next() != Some(')') {
+                return Err("Group was not closed".to_string());
+            }
+
+            set
+        }
+
+        // Character set
+        '[' => parse_set(chars)?,
+
+        // Escaped character
+        '\\' => vec![parse_escape(chars)?.to_string()],
+
+        // Metacharacters that do not match a finite list of segments
+        '.' | '^' | '$' => return Err(format!("Unsupported character `{ch}`")),
+        '*' | '+' | '?' | '{' => return Err(format!("Unexpected quantifier `{ch}`")),
+
+        // Literal character
+        _ => vec![ch.to_string()],
+    })
+}
+
+/// Parse optional quantifier after an atom, and apply it
+fn parse_quantifier(chars: &mut Peekable<Chars>, atom: Vec<String>) -> Result<Vec<String>, String> {
+    let (min, max) = match chars.peek() {
+        Some('?') => {
+            chars.next();
+            (0, 1)
+        }
+
+        Some('{') => {
+            chars.next();
+
+            let mut body = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(ch) => body.push(ch),
+                    None => return Err("Quantifier was not closed".to_string()),
+                }
+            }
+
+            let parse_number = |s: &str| {
+                s.parse::<usize>()
+                    .map_err(|_| format!("Invalid or unbounded quantifier `{{{body}}}`"))
+            };
+
+            match body.split_once(',') {
+                None => {
+                    let n = parse_number(&body)?;
+                    (n, n)
+                }
+                Some((min, max)) => (parse_number(min)?, parse_number(max)?),
+            }
+        }
+
+        Some(&ch @ ('*' | '+')) => return Err(format!("Unbounded quantifier `{ch}`")),
+
+        // No quantifier
+        _ => return Ok(atom),
+    };
+
+    // Lazy quantifier does not change what can be matched
+    if chars.peek() == Some(&'?') {
+        chars.next();
+    }
+
+    if min > max {
+        return Err(format!("Invalid quantifier range `{{{min},{max}}}`"));
+    }
+
+    // Union of atom repeated each amount of times in range
+    let mut set = Vec::new();
+    let mut repeated = vec![String::new()];
+    for count in 0..=max {
+        if count >= min {
+            set.extend(repeated.iter().cloned());
+            check_size(&set)?;
+        }
+        if count == max {
+            break;
+        }
+        repeated = product(&repeated, &atom);
+        check_size(&repeated)?;
+    }
+
+    Ok(set)
+}
+
+/// Parse character set, after opening square bracket
+///
+/// Nested character sets and non-capturing groups inside the set are flattened into their union,
+/// so that a class such as `[<C><V>]` contains the segments of both classes
+fn parse_set(chars: &mut Peekable<Chars>) -> Result<Vec<String>, String> {
+    if chars.peek() == Some(&'^') {
+        return Err("Negated character sets are not supported".to_string());
+    }
+
+    let mut set: Vec<String> = Vec::new();
+    let mut first = true;
+
+    loop {
+        let ch = match chars.next() {
+            // Closing bracket is literal if first in set
+            Some(']') if !first => break,
+            Some('\\') => parse_escape(chars)?,
+
+            // Nested character set
+            Some('[') => {
+                first = false;
+                set.extend(parse_set(chars)?);
+                check_size(&set)?;
+                continue;
+            }
+
+            // Non-capturing group, such as a substituted class
+            Some('(') if chars.peek() == Some(&'?') => {
+                chars.next();
+                if chars.next() != Some(':') {
+                    return Err(
+                        "Only non-capturing groups are supported in a character set".to_string()
+                    );
+                }
+
+                set.extend(parse_alternation(chars)?);
+                if chars.next() != Some(')') {
+                    return Err("Group was not closed".to_string());
+                }
+
+                first = false;
+                check_size(&set)?;
+                continue;
+            }
+
+            Some(ch) => ch,
+            None => return Err("Character set was not closed".to_string()),
+        };
+        first = false;
+
+        // Range of characters
+        if chars.peek() == Some(&'-') {
+            chars.next();
+
+            let end = match chars.next() {
+                // Hyphen is literal at end of set
+                Some(']') => {
+                    set.push(ch.to_string());
+                    set.push("-".to_string());
+                    break;
+                }
+                Some('\\') => parse_escape(chars)?,
+                Some(end) => end,
+                None => return Err("Character set was not closed".to_string()),
+            };
+
+            if ch > end {
+                return Err(format!("Invalid character range `{ch}-{end}`"));
+            }
+            set.extend((ch..=end).map(|ch| ch.to_string()));
+        } else {
+            set.push(ch.to_string());
+        }
+
+        check_size(&set)?;
+    }
+
+    Ok(set)
+}
+
+/// Parse escaped character, after backslash
+///
+/// Only escaped punctuation is supported, not character classes such as `\w`
+fn parse_escape(chars: &mut Peekable<Chars>) -> Result<char, String> {
+    match chars.next() {
+        Some(ch) if ch.is_ascii_punctuation() || ch == ' ' => Ok(ch),
+        Some(ch) => Err(format!("Unsupported escape sequence `\\{ch}`")),
+        None => Err("Unexpected end of pattern".to_string()),
+    }
+}
+
+/// Concatenate every string of `left` with every string of `right`
+fn product(left: &[String], right: &[String]) -> Vec<String> {
+    let mut set = Vec::with_capacity(left.len() * right.len());
+    for a in left {
+        for b in right {
+            set.push(a.to_string() + b);
+        }
+    }
+    set
+}
+
+/// Return error if set has too many segments
+fn check_size(set: &[String]) -> Result<(), String> {
+    if set.len() > MAX_SEGMENTS {
+        return Err(too_many_segments());
+    }
+    Ok(())
+}
+
+fn too_many_segments() -> String {
+    format!("Class expands to more than {MAX_SEGMENTS} segments")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_works() {
+        assert_eq!(expand("[ptk]").unwrap(), vec!["p", "t", "k"]);
+        assert_eq!(expand("(?:[a-c])").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(expand("(?:[pt]|sh|ch)").unwrap(), vec!["p", "t", "sh", "ch"]);
+        assert_eq!(expand("s[hz]?").unwrap(), vec!["s", "sh", "sz"]);
+        assert_eq!(expand("(?:(?:[pt])|(?:[ai]))").unwrap(), vec!["p", "t", "a", "i"]);
+        assert_eq!(expand("[ab]{2}").unwrap(), vec!["aa", "ab", "ba", "bb"]);
+        assert_eq!(expand("a{0,1}b").unwrap(), vec!["b", "ab"]);
+        assert_eq!(expand("[a-]").unwrap(), vec!["a", "-"]);
+        assert_eq!(expand(r"\.x").unwrap(), vec![".x"]);
+        assert_eq!(expand("[pp]|p").unwrap(), vec!["p"]);
+        assert_eq!(expand("[ʃʒ]").unwrap(), vec!["ʃ", "ʒ"]);
+        assert_eq!(expand("[(?:[pt])(?:[ai])]").unwrap(), vec!["p", "t", "a", "i"]);
+        assert_eq!(expand("[x[a-c]]").unwrap(), vec!["x", "a", "b", "c"]);
+        assert_eq!(expand("[(?:[pt]|sh)]").unwrap(), vec!["p", "t", "sh"]);
+
+        assert!(expand("").is_err());
+        assert!(expand("[^a]").is_err());
+        assert!(expand("a+").is_err());
+        assert!(expand("a*").is_err());
+        assert!(expand("a{2,}").is_err());
+        assert!(expand(".").is_err());
+        assert!(expand(r"\w").is_err());
+        assert!(expand("(a").is_err());
+        assert!(expand("a)").is_err());
+        assert!(expand("[ab").is_err());
+        assert!(expand("[(?:a]").is_err());
+        assert!(expand("[(?=a)]").is_err());
+        assert!(expand("(?=a)").is_err());
+        assert!(expand("[a-z]{4}").is_err());
+    }
+}
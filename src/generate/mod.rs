@@ -0,0 +1,336 @@
+/// Count valid words of each length
+mod capacity;
+/// Walk every valid word
+mod enumerate;
+/// Expand classes into lists of segments
+mod inventory;
+
+use std::{
+    collections::HashMap,
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub use capacity::{Capacity, CapacityCount, CapacityOptions};
+pub use enumerate::Enumerate;
+
+use crate::{
+    types::{Error, SyllablePart, Weights},
+    Phonet,
+};
+
+/// Options for generating random words
+///
+/// Use with `Phonet::generate_with`
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    /// Range of word lengths, in characters
+    ///
+    /// Not used if `syllables` is given
+    pub length: Range<usize>,
+    /// Range of word lengths, in syllables
+    ///
+    /// If given, words are built from the syllable templates of the scheme, instead of the 'any' class
+    pub syllables: Option<Range<usize>>,
+    /// Maximum amount of candidate words to try for each word, before stopping
+    ///
+    /// Attempts are counted again from zero after each word is generated
+    ///
+    /// Use `None` for no limit
+    pub max_attempts: Option<usize>,
+    /// Maximum time to spend generating words
+    ///
+    /// Use `None` for no limit
+    pub timeout: Option<Duration>,
+    /// Seed for random number generator
+    ///
+    /// The same scheme, seed, count and length will always generate the same words, on any platform
+    ///
+    /// Use `None` for a random seed
+    pub seed: Option<u64>,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            length: 3..14,
+            syllables: None,
+            max_attempts: Some(100_000),
+            timeout: None,
+            seed: None,
+        }
+    }
+}
+
+impl Phonet {
+    /// Generate random words that fit the rules
+    ///
+    /// Words are built from the segments of the 'any' class (`$_`), which may include multi-character
+    /// segments such as `sh`, and are then checked against the rules
+    ///
+    /// Uses default attempt limit of `GenerateOptions`
+    pub fn generate(&self, count: usize, length: Range<usize>) -> Result<Vec<String>, Error> {
+        self.generate_with(
+            count,
+            &GenerateOptions {
+                length,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Generate random words that fit the rules, with options
+    ///
+    /// If the attempt limit or timeout is reached, returns `Error::GenerationExhausted`, which holds
+    /// the words generated so far
+    ///
+    /// Returns `Error::EmptyLengthRange` if the range of lengths (or of syllables) is empty
+    pub fn generate_with(
+        &self,
+        count: usize,
+        options: &GenerateOptions,
+    ) -> Result<Vec<String>, Error> {
+        // Range to choose lengths from, in characters or syllables
+        let range = options.syllables.as_ref().unwrap_or(&options.length);
+        if range.is_empty() {
+            return Err(Error::EmptyLengthRange {
+                start: range.start,
+                end: range.end,
+            });
+        }
+
+        // Segments of 'any' class, or of every class used in syllable templates
+        let inventories = match options.syllables {
+            None => HashMap::from([("_", self.inventory("_")?)]),
+            Some(_) => self.syllable_inventories()?,
+        };
+
+        let mut words = Vec::new();
+        let mut rng = match options.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+
+        let start = Instant::now();
+        // Amount of candidates tried, in total and since last word was generated
+        let mut attempts = 0;
+        let mut word_attempts = 0;
+        // Amount of candidates rejected by each rule
+        let mut rejections = vec![0usize; self.rules.len()];
+
+        while words.len() < count {
+            // Check budget
+            if options.max_attempts.is_some_and(|max| word_attempts >= max)
+                || options.timeout.is_some_and(|max| start.elapsed() >= max)
+            {
+                return Err(self.exhausted(count, attempts, words, &rejections));
+            }
+            attempts += 1;
+            word_attempts += 1;
+
+            let word = match &options.syllables {
+                None => {
+                    let length = random_in(&options.length, &mut rng);
+                    random_word(&inventories["_"], &self.weights, length, &mut rng)
+                }
+                Some(syllables) => {
+                    let count = random_in(syllables, &mut rng);
+                    self.random_syllables(&inventories, count, &mut rng)
+                }
+            };
+            let Some(word) = word else {
+                continue;
+            };
+
+            match self.first_failing_rule(&word) {
+                Some(index) => rejections[index] += 1,
+                None => {
+                    words.push(word);
+                    word_attempts = 0;
+                }
+            }
+        }
+
+        Ok(words)
+    }
+
+    /// Create error for generation running out of attempts or time
+    fn exhausted(
+        &self,
+        requested: usize,
+        attempts: usize,
+        words: Vec<String>,
+        rejections: &[usize],
+    ) -> Error {
+        // Rule which rejected the most candidates
+        let rule = rejections
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .max_by_key(|(index, count)| (**count, std::cmp::Reverse(*index)))
+            .map(|(index, _)| &self.rules[index]);
+
+        Error::GenerationExhausted {
+            requested,
+            attempts,
+            words,
+            rejected_by: rule.map(|rule| rule.line),
+            reason: rule
+                .and_then(|rule| rule.reason_ref)
+                .and_then(|reason| self.reasons.get(reason))
+                .cloned(),
+        }
+    }
+
+    /// Get segments of every class used in syllable templates
+    fn syllable_inventories(&self) -> Result<HashMap<&str, Vec<String>>, Error> {
+        if self.syllables.is_empty() {
+            return Err(Error::MissingSyllables);
+        }
+
+        let mut names = Vec::new();
+        for syllable in &self.syllables {
+            for part in &syllable.parts {
+                part.class_names(&mut names);
+            }
+        }
+
+        let mut inventories = HashMap::new();
+        for name in names {
+            if !inventories.contains_key(name) {
+                inventories.insert(name, self.inventory(name)?);
+            }
+        }
+
+        Ok(inventories)
+    }
+
+    /// Generate random word from given amount of syllables, each using a random syllable template
+    ///
+    /// Returns `None` if a class part could not choose a segment
+    fn random_syllables(
+        &self,
+        inventories: &HashMap<&str, Vec<String>>,
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Option<String> {
+        let mut word = String::new();
+
+        for _ in 0..count {
+            let syllable = self.syllables.choose(rng)?;
+            for part in &syllable.parts {
+                self.push_part(&mut word, part, inventories, rng)?;
+            }
+        }
+
+        Some(word)
+    }
+
+    /// Add random text for syllable template part to word
+    fn push_part(
+        &self,
+        word: &mut String,
+        part: &SyllablePart,
+        inventories: &HashMap<&str, Vec<String>>,
+        rng: &mut impl Rng,
+    ) -> Option<()> {
+        match part {
+            SyllablePart::Class(name) => {
+                let segments: Vec<&String> = inventories.get(name.as_str())?.iter().collect();
+                word.push_str(choose_segment(&segments, &self.weights, rng)?);
+            }
+
+            SyllablePart::Literal(text) => word.push_str(text),
+
+            SyllablePart::Group(parts) => {
+                for part in parts {
+                    self.push_part(word, part, inventories, rng)?;
+                }
+            }
+
+            // Include part half of the time
+            SyllablePart::Optional(part) => {
+                if rng.gen_bool(0.5) {
+                    self.push_part(word, part, inventories, rng)?;
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    /// Get list of segments that a class can match
+    ///
+    /// Class value must only use finite patterns, such as `[ptk]` or `(?:[ptk]|sh)`
+    pub(crate) fn inventory(&self, name: &str) -> Result<Vec<String>, Error> {
+        let Some(value) = self.classes.get(name) else {
+            return Err(match name {
+                "_" => Error::MissingAnyClass,
+                _ => Error::InvalidInventory {
+                    name: name.to_string(),
+                    reason: "Class is not defined".to_string(),
+                },
+            });
+        };
+
+        inventory::expand(value).map_err(|reason| Error::InvalidInventory {
+            name: name.to_string(),
+            reason,
+        })
+    }
+}
+
+/// Choose random number in range, which must not be empty
+///
+/// Samples a `u64`, as sampling a `usize` gives different numbers on 32-bit and 64-bit platforms
+fn random_in(range: &Range<usize>, rng: &mut impl Rng) -> usize {
+    rng.gen_range(range.start as u64..range.end as u64) as usize
+}
+
+/// Generate random word from segments, with given length in characters
+///
+/// Segments are chosen using their weights, with a default weight of `1.0`
+///
+/// Returns `None` if no combination of segments could fill the length exactly
+fn random_word(
+    segments: &[String],
+    weights: &Weights,
+    length: usize,
+    rng: &mut impl Rng,
+) -> Option<String> {
+    let mut word = String::new();
+    let mut word_len = 0;
+
+    while word_len < length {
+        // Only use segments that fit in remaining length
+        let fitting: Vec<&String> = segments
+            .iter()
+            .filter(|segment| segment.chars().count() <= length - word_len)
+            .collect();
+
+        // Fails if no segments fit, or all fitting segments have a weight of zero
+        let segment = choose_segment(&fitting, weights, rng)?;
+
+        word.push_str(segment);
+        word_len += segment.chars().count();
+    }
+
+    Some(word)
+}
+
+/// Choose random segment, using weights of segments, with a default weight of `1.0`
+///
+/// Returns `None` if list is empty, or all segments have a weight of zero
+fn choose_segment<'a>(
+    segments: &[&'a String],
+    weights: &Weights,
+    rng: &mut impl Rng,
+) -> Option<&'a String> {
+    segments
+        .choose_weighted(rng, |segment| weights.get(*segment).copied().unwrap_or(1.0))
+        .ok()
+        .copied()
+}
@@ -1,12 +1,33 @@
+/// Validates many words in parallel
+mod batch;
+/// Handles rule coverage of tests
+mod coverage;
 /// Generate random word
 mod generate;
+/// Finds possible mistakes in files
+mod lint;
+/// Combined matcher of rules, for faster validation
+mod matcher;
+/// Formats results as machine-readable text
+mod output;
 /// Handles all parsing of `phonet` files
 mod parse;
 /// Handles running of tests
 mod run;
+/// Lossless syntax tree of files, and formatting
+mod syntax;
 /// Holds simple types and structs
 mod types;
 
+pub use batch::{Batch, BatchOptions};
+pub use coverage::{Coverage, RuleCoverage};
+pub use generate::{Capacity, CapacityCount, CapacityOptions, Enumerate, GenerateOptions};
+pub use lint::{Lint, Warning};
+pub use output::format_files;
 pub use parse::Phonet;
-pub use run::{Results, ValidStatus};
-pub use types::{DisplayLevel, Error, FailReason, TestDefinition, TestResult};
+pub use run::{Results, RunOptions, ValidStatus, Violation};
+pub use syntax::{format_file, End, Kind, Node, SyntaxTree};
+pub use types::{
+    DisplayLevel, Error, FailReason, OutputFormat, Span, Syllable, SyllablePart, TestDefinition,
+    TestResult,
+};
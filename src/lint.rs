@@ -0,0 +1,489 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    parse::{class_references, split_statements, Statement},
+    types::{render_diagnostic, Span},
+    Phonet,
+};
+
+/// Kind of warning found by linting
+///
+/// Warnings can be allowed for a whole file with a comment, using the code or name of the lint,
+/// such as `#! allow unused-class W001`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// Class is not used by any rule, syllable template, or the 'any' class
+    ///
+    /// Not checked for files which include other files, as included rules may use the class
+    UnusedClass,
+    /// Rule is the same as an earlier rule
+    DuplicateRule,
+    /// Reason is not used by any rule
+    UnusedReason,
+    /// Test is the same as an earlier test
+    DuplicateTest,
+    /// Word is tested as both valid and invalid
+    ContradictoryTest,
+    /// Class has segments which are not in the 'any' class
+    UncoveredSegment,
+    /// Weight is given for a segment which is not in any class
+    UnknownWeight,
+}
+
+impl Lint {
+    /// Every kind of lint
+    pub const ALL: [Lint; 7] = [
+        Lint::UnusedClass,
+        Lint::DuplicateRule,
+        Lint::UnusedReason,
+        Lint::DuplicateTest,
+        Lint::ContradictoryTest,
+        Lint::UncoveredSegment,
+        Lint::UnknownWeight,
+    ];
+
+    /// Get short code of lint
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lint::UnusedClass => "W001",
+            Lint::DuplicateRule => "W002",
+            Lint::UnusedReason => "W003",
+            Lint::DuplicateTest => "W004",
+            Lint::ContradictoryTest => "W005",
+            Lint::UncoveredSegment => "W006",
+            Lint::UnknownWeight => "W007",
+        }
+    }
+
+    /// Get name of lint
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedClass => "unused-class",
+            Lint::DuplicateRule => "duplicate-rule",
+            Lint::UnusedReason => "unused-reason",
+            Lint::DuplicateTest => "duplicate-test",
+            Lint::ContradictoryTest => "contradictory-test",
+            Lint::UncoveredSegment => "uncovered-segment",
+            Lint::UnknownWeight => "unknown-weight",
+        }
+    }
+
+    /// Get lint from code or name
+    pub fn from_code(code: &str) -> Option<Lint> {
+        Self::ALL
+            .into_iter()
+            .find(|lint| lint.code().eq_ignore_ascii_case(code) || lint.name() == code)
+    }
+}
+
+/// Warning of possible mistake in file
+#[derive(Debug)]
+pub struct Warning {
+    /// Kind of warning
+    pub lint: Lint,
+    /// Description of warning
+    pub message: String,
+    /// Position of warning in file
+    pub span: Span,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{message} [{code} {name}], at line {line}",
+            message = self.message,
+            code = self.lint.code(),
+            name = self.lint.name(),
+            line = self.span.line,
+        )
+    }
+}
+
+impl Warning {
+    /// Format warning with line of source file, marking position of warning
+    ///
+    /// `source` should be the file that was linted
+    pub fn render(&self, source: &str, no_color: bool) -> String {
+        render_diagnostic(
+            ("warning", "33;1"),
+            &format!(
+                "{message} [{code} {name}]",
+                message = self.message,
+                code = self.lint.code(),
+                name = self.lint.name(),
+            ),
+            Some(self.span),
+            Some(&format!("allow with `#! allow {}`", self.lint.name())),
+            source,
+            no_color,
+        )
+    }
+}
+
+impl Phonet {
+    /// Check file for possible mistakes, which are not errors
+    ///
+    /// `file` should be the file that this scheme was parsed from
+    ///
+    /// Returns warnings in order of position in file, without warnings allowed in file
+    pub fn lint(&self, file: &str) -> Vec<Warning> {
+        let mut linter = Linter::default();
+
+        for statement in split_statements(file) {
+            linter.statement(&statement);
+        }
+
+        linter.finish(self)
+    }
+}
+
+/// Holds definitions while linting statements
+#[derive(Default)]
+struct Linter {
+    /// Lints allowed in file
+    allowed: HashSet<Lint>,
+    /// Name, position of name, and raw value, of each class, in order of definition
+    classes: Vec<(String, Span, String)>,
+    /// Names of classes used by rules and syllable templates
+    used: HashSet<String>,
+    /// Whether file includes other files
+    includes: bool,
+    /// Line of each rule, by intent and pattern without spaces
+    rules: HashMap<(bool, String), usize>,
+    /// Position of last reason, if not used by any rule yet
+    pending_reason: Option<Span>,
+    /// Intent and line of each test word
+    tests: HashMap<String, (bool, usize)>,
+    /// Segment and position of each weight
+    weights: Vec<(String, Span)>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    /// Check a single statement
+    fn statement(&mut self, statement: &Statement) {
+        let text = statement.text.trim();
+        let mut chars = text.chars();
+
+        let Some(first) = chars.next() else {
+            return;
+        };
+        let rest = chars.as_str();
+
+        match first {
+            // Comment, which may allow lints
+            '#' => {
+                let Some(codes) = rest
+                    .strip_prefix('!')
+                    .and_then(|rest| rest.trim_start().strip_prefix("allow"))
+                else {
+                    return;
+                };
+
+                for code in codes.split(|ch: char| ch == ',' || ch.is_whitespace()) {
+                    if let Some(lint) = Lint::from_code(code) {
+                        self.allowed.insert(lint);
+                    }
+                }
+            }
+
+            // Include
+            '<' => self.includes = true,
+
+            // Weights
+            '%' => {
+                for item in rest.split_whitespace() {
+                    if let Some((segment, _)) = item.rsplit_once(':') {
+                        self.weights
+                            .push((segment.to_string(), statement.span(segment)));
+                    }
+                }
+            }
+
+            // Class
+            '$' => {
+                let mut split = rest.split('=');
+                let (Some(name), Some(value)) = (split.next(), split.next()) else {
+                    return;
+                };
+
+                self.classes.push((
+                    name.trim().to_string(),
+                    statement.span(name.trim()),
+                    value.to_string(),
+                ));
+            }
+
+            // Rule
+            '+' | '!' => {
+                let pattern = rest.trim();
+                let key = (
+                    first == '+',
+                    pattern.replace(' ', "").replace('⟨', "<").replace('⟩', ">"),
+                );
+
+                if let Some(line) = self.rules.get(&key) {
+                    self.warn(
+                        Lint::DuplicateRule,
+                        format!("Rule is the same as rule at line {line}"),
+                        statement.span(pattern),
+                    );
+                } else {
+                    self.rules.insert(key, statement.line);
+                }
+
+                self.used
+                    .extend(class_references(pattern).into_iter().map(|(name, _)| name));
+                self.pending_reason = None;
+            }
+
+            // Syllable template
+            '=' => {
+                self.used
+                    .extend(class_references(rest).into_iter().map(|(name, _)| name));
+            }
+
+            // Reason
+            '@' => {
+                if let Some(span) = self.pending_reason {
+                    self.unused_reason(span);
+                }
+
+                let reason = rest.trim_start();
+                let reason = reason.strip_prefix('*').unwrap_or(reason).trim();
+                self.pending_reason = Some(statement.span(reason));
+            }
+
+            // Test
+            '?' => {
+                let mut chars = rest.trim_start().chars();
+                let intent = match chars.next() {
+                    Some('+') => true,
+                    Some('!') => false,
+                    _ => return,
+                };
+
+                for word in chars.as_str().split_whitespace() {
+                    let span = statement.span(word);
+
+                    let Some(&(first_intent, line)) = self.tests.get(word) else {
+                        self.tests.insert(word.to_string(), (intent, span.line));
+                        continue;
+                    };
+
+                    if first_intent == intent {
+                        self.warn(
+                            Lint::DuplicateTest,
+                            format!("Test `{word}` is the same as test at line {line}"),
+                            span,
+                        );
+                    } else {
+                        self.warn(
+                            Lint::ContradictoryTest,
+                            format!(
+                                "Word `{word}` is tested as {}, but as {} at line {line}",
+                                display_intent(intent),
+                                display_intent(first_intent),
+                            ),
+                            span,
+                        );
+                    }
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    /// Check definitions of whole file, and get warnings
+    fn finish(mut self, scheme: &Phonet) -> Vec<Warning> {
+        if let Some(span) = self.pending_reason {
+            self.unused_reason(span);
+        }
+
+        // Classes may be used by included rules
+        if !self.includes {
+            self.unused_classes();
+        }
+        self.uncovered_segments(scheme);
+        self.unknown_weights(scheme);
+
+        let mut warnings: Vec<_> = self
+            .warnings
+            .into_iter()
+            .filter(|warning| !self.allowed.contains(&warning.lint))
+            .collect();
+        warnings.sort_by_key(|warning| (warning.span.line, warning.span.column));
+        warnings
+    }
+
+    /// Add warning
+    fn warn(&mut self, lint: Lint, message: String, span: Span) {
+        self.warnings.push(Warning {
+            lint,
+            message,
+            span,
+        });
+    }
+
+    /// Add warning for reason which is not used by any rule
+    fn unused_reason(&mut self, span: Span) {
+        self.warn(
+            Lint::UnusedReason,
+            "Reason is not used by any rule".to_string(),
+            span,
+        );
+    }
+
+    /// Add warnings for classes which are not used by any rule or syllable template, even through
+    /// other classes
+    fn unused_classes(&mut self) {
+        let values: HashMap<&str, &str> = self
+            .classes
+            .iter()
+            .map(|(name, _, value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        // Add classes used by used classes
+        // 'Any' class is always used, for generating words
+        let mut used: HashSet<String> = HashSet::new();
+        let mut stack: Vec<String> = self.used.iter().cloned().collect();
+        stack.push("_".to_string());
+
+        while let Some(name) = stack.pop() {
+            if !used.insert(name.clone()) {
+                continue;
+            }
+            if let Some(value) = values.get(name.as_str()) {
+                stack.extend(class_references(value).into_iter().map(|(name, _)| name));
+            }
+        }
+
+        let unused: Vec<_> = self
+            .classes
+            .iter()
+            .filter(|(name, ..)| !used.contains(name))
+            .map(|(name, span, _)| (name.clone(), *span))
+            .collect();
+
+        for (name, span) in unused {
+            self.warn(
+                Lint::UnusedClass,
+                format!("Class `{name}` is not used by any rule"),
+                span,
+            );
+        }
+    }
+
+    /// Add warnings for classes with segments which are not in the 'any' class
+    ///
+    /// Classes which cannot be expanded to a list of segments are skipped
+    fn uncovered_segments(&mut self, scheme: &Phonet) {
+        let Ok(any) = scheme.inventory("_") else {
+            return;
+        };
+
+        let mut uncovered = Vec::new();
+        for (name, span, _) in &self.classes {
+            if name == "_" {
+                continue;
+            }
+            let Ok(segments) = scheme.inventory(name) else {
+                continue;
+            };
+
+            let missing: Vec<_> = segments
+                .iter()
+                .filter(|segment| !is_composed_of(segment, &any))
+                .map(|segment| format!("`{segment}`"))
+                .collect();
+
+            if !missing.is_empty() {
+                uncovered.push((name.clone(), *span, missing.join(", ")));
+            }
+        }
+
+        for (name, span, missing) in uncovered {
+            self.warn(
+                Lint::UncoveredSegment,
+                format!("Class `{name}` has segments which are not in the 'any' class: {missing}"),
+                span,
+            );
+        }
+    }
+
+    /// Add warnings for weights of segments which are not in any class
+    fn unknown_weights(&mut self, scheme: &Phonet) {
+        let unknown = scheme.unknown_weights();
+        let spans: Vec<_> = self
+            .weights
+            .iter()
+            .filter(|(segment, _)| unknown.contains(&segment.as_str()))
+            .cloned()
+            .collect();
+
+        for (segment, span) in spans {
+            self.warn(
+                Lint::UnknownWeight,
+                format!("Weight is given for segment `{segment}`, which is not in any class"),
+                span,
+            );
+        }
+    }
+}
+
+/// Returns `true` if word can be split into segments
+fn is_composed_of(word: &str, segments: &[String]) -> bool {
+    // Whether prefix of word, by length in bytes, can be split into segments
+    let mut reachable = vec![false; word.len() + 1];
+    reachable[0] = true;
+
+    for start in 0..word.len() {
+        if !reachable[start] {
+            continue;
+        }
+        for segment in segments {
+            if !segment.is_empty() && word[start..].starts_with(segment.as_str()) {
+                reachable[start + segment.len()] = true;
+            }
+        }
+    }
+
+    reachable[word.len()]
+}
+
+/// Display intent of test
+fn display_intent(intent: bool) -> &'static str {
+    if intent {
+        "valid"
+    } else {
+        "invalid"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_composed_of_works() {
+        let segments = vec!["s".to_string(), "h".to_string(), "a".to_string()];
+
+        assert!(is_composed_of("sh", &segments));
+        assert!(is_composed_of("a", &segments));
+        assert!(!is_composed_of("ch", &segments));
+        assert!(!is_composed_of("ø", &segments));
+    }
+
+    #[test]
+    fn lint_from_code_works() {
+        assert_eq!(Lint::from_code("W001"), Some(Lint::UnusedClass));
+        assert_eq!(Lint::from_code("w005"), Some(Lint::ContradictoryTest));
+        assert_eq!(Lint::from_code("duplicate-rule"), Some(Lint::DuplicateRule));
+        assert_eq!(Lint::from_code("unknown"), None);
+    }
+}
@@ -1,24 +1,219 @@
 mod args;
+mod words;
 
-use std::fs;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    fs, io,
+    path::Path,
+    process::ExitCode,
+    time::Duration,
+};
 
-use args::Args;
-use clap::Parser;
-use phonet::{TestDefinition, Phonet};
+use args::{Args, Command};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use phonet::{
+    format_file, format_files, BatchOptions, CapacityCount, CapacityOptions, DisplayLevel, Error,
+    GenerateOptions, OutputFormat, Phonet, Results, RunOptions, TestDefinition,
+};
+use words::read_words;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// Print line to standard output, or to standard error if standard output has machine-readable
+/// results
+macro_rules! outln {
+    ($human:expr, $($arg:tt)*) => {
+        if $human {
+            println!($($arg)*);
+        } else {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Exit status of program
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    /// Everything succeeded
+    Success = 0,
+    /// Some tests failed, no tests ran with `--fail-on-empty`, or some words of word list are
+    /// invalid
+    TestsFailed = 1,
+    /// File could not be parsed
+    ParseError = 2,
+    /// File could not be read or written
+    IoError = 3,
+    /// Words could not be generated, listed, or counted
+    GenerationFailed = 4,
+    /// Lint found some warnings
+    LintWarnings = 5,
+    /// File is not formatted, when checking format
+    NotFormatted = 6,
+}
+
+impl Status {
+    /// Set status if no failure has been set yet
+    fn fail(&mut self, status: Status) {
+        if *self == Status::Success {
+            *self = status;
+        }
+    }
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
+    // Subcommands have no machine-readable output
+    if args.command.is_some() && !matches!(args.format, OutputFormat::Human) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "`--format` cannot be used with a subcommand",
+            )
+            .exit();
+    }
+
+    ExitCode::from(run(args) as u8)
+}
+
+/// Run program with arguments, return exit status
+fn run(args: Args) -> Status {
+    let paths = match find_files(&args.files) {
+        Ok(paths) => paths,
+        Err(err) => {
+            print_error(err, args.no_color);
+            return Status::IoError;
+        }
+    };
+
+    // Read word list once, as it can be read from standard input
+    let words = match &args.command {
+        Some(Command::Words {
+            input,
+            column,
+            header,
+            ..
+        }) => match read_words(input.as_deref(), *column, *header) {
+            Ok(words) => words,
+            Err(err) => {
+                print_error(err, args.no_color);
+                return Status::IoError;
+            }
+        },
+        _ => Vec::new(),
+    };
+
+    // Single file is displayed without section or summary
+    if let [path] = paths.as_slice() {
+        return run_file(&args, path, &words, false).0;
+    }
+
+    let human = matches!(args.format, OutputFormat::Human);
+    let mut status = Status::Success;
+    let mut outcomes = Vec::new();
+
+    for (i, path) in paths.iter().enumerate() {
+        // Display section for each file
+        if human {
+            if i > 0 {
+                println!();
+            }
+            if args.no_color {
+                println!("File `{path}`");
+            } else {
+                println!("\x1b[34;1;4mFile `{path}`\x1b[0m");
+            }
+        }
+
+        let (file_status, results) = run_file(&args, path, &words, true);
+        status.fail(file_status);
+        outcomes.push((path.as_str(), file_status, results));
+    }
+
+    if human {
+        summary(&outcomes, args.no_color);
+    } else {
+        // Machine-readable results of every file, which could be tested
+        let files: Vec<_> = outcomes
+            .iter()
+            .filter_map(|(path, _, results)| Some((*path, results.as_ref()?)))
+            .collect();
+        if let Some(output) = format_files(&files, args.format) {
+            print_output(&output);
+        }
+    }
+
+    status
+}
+
+/// Run a single file, return exit status, and results of tests if tests were run
+///
+/// `words` is the word list for the `words` command. If `combined` is `true`, machine-readable
+/// results are not displayed, as they are combined with results of other files
+fn run_file(
+    args: &Args,
+    path: &str,
+    words: &[String],
+    combined: bool,
+) -> (Status, Option<Results>) {
+    let mut status = Status::Success;
+
     // Read file
-    let file = fs::read_to_string(&args.file)?;
+    let file = match fs::read_to_string(path) {
+        Ok(file) => file,
+        Err(err) => {
+            print_error(
+                format!("Could not read file `{path}`: {err}"),
+                args.no_color,
+            );
+            return (Status::IoError, None);
+        }
+    };
 
-    // Parse file
-    let mut scheme = Phonet::parse(&file)
-        .map_err(|err| err.to_string())
-        .expect("Failed to parse file");
+    // Parse file, displaying every error
+    let (mut scheme, errors) = Phonet::parse_recover_at(&file, path);
+    if !errors.is_empty() {
+        for err in &errors {
+            eprintln!("{}", err.render(&file, args.no_color));
+        }
+        print_error(
+            format!(
+                "Failed to parse file, with {count} error{s}",
+                count = errors.len(),
+                s = if errors.len() == 1 { "" } else { "s" },
+            ),
+            args.no_color,
+        );
+        return (Status::ParseError, None);
+    }
+
+    // Check file for possible mistakes, format file, or check word list, instead of running tests
+    match &args.command {
+        Some(Command::Lint) => return (lint(&scheme, &file, args.no_color), None),
+        Some(Command::Fmt { check }) => return (fmt(path, &file, *check, args.no_color), None),
+        Some(Command::Words { split, threads, .. }) => {
+            // Name files of each scheme differently
+            let prefix = match Path::new(path).file_stem() {
+                Some(stem) if combined => format!("{}.", stem.to_string_lossy()),
+                _ => String::new(),
+            };
+            let split = split.as_ref().map(|dir| (Path::new(dir), prefix.as_str()));
+            return (
+                check_words(
+                    &scheme,
+                    words,
+                    &BatchOptions { threads: *threads },
+                    split,
+                    args.display_level,
+                    args.no_color,
+                ),
+                None,
+            );
+        }
+        None => (),
+    }
 
     // Use CLI tests if given
-    if let Some(tests) = args.tests {
+    if let Some(tests) = &args.tests {
         scheme.tests = tests
             .split(',')
             .map(|x| TestDefinition::Test {
@@ -30,63 +225,527 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Minify file
     if let Some(do_tests) = args.minify {
-        fs::write(
-            get_min_filename(&args.file),
-            scheme.minify(do_tests.is_some()),
-        )?;
+        let min_file = get_min_filename(path);
+        if let Err(err) = fs::write(&min_file, scheme.minify(do_tests.is_some())) {
+            print_error(
+                format!("Could not write file `{min_file}`: {err}"),
+                args.no_color,
+            );
+            return (Status::IoError, None);
+        }
     }
 
     // Run tests and display
     let test_count = scheme.test_count();
-    if test_count > 0 {
+    let human = matches!(args.format, OutputFormat::Human);
+    if test_count > 0 && human {
         if args.no_color {
             println!("Running {} tests...", test_count);
         } else {
             println!("\x1b[3;33mRunning {} tests...\x1b[0m", test_count);
         }
     }
-    scheme.run().display(args.display_level, args.no_color);
+    let results = scheme.run_with(&RunOptions {
+        all_violations: args.all_violations,
+        coverage: args.coverage,
+    });
+    if results.fail_count > 0 {
+        status.fail(Status::TestsFailed);
+    }
+    if test_count == 0 && args.fail_on_empty {
+        print_error("No tests to run", args.no_color);
+        status.fail(Status::TestsFailed);
+    }
+    match results.format(args.format, path) {
+        // Machine-readable results of multiple files are displayed together
+        Some(_) if combined => (),
+        // Machine-readable format includes coverage
+        Some(output) => print_output(&output),
+
+        None => {
+            results.display(args.display_level, args.no_color);
+
+            // Display coverage of rules, if CLI arg given
+            if let Some(coverage) = &results.coverage {
+                coverage.display(args.no_color);
+            }
+        }
+    }
 
     // Generate and display random words, if CLI arg given
     if let Some(count) = args.generate {
         let count = count.unwrap_or(1);
 
-        // Min and max length
-        let length = args.generate_min_len.unwrap_or(3)..args.generate_max_len.unwrap_or(14);
+        let defaults = GenerateOptions::default();
+        let options = GenerateOptions {
+            // Min and max length
+            length: args.generate_min_len.unwrap_or(defaults.length.start)
+                ..args.generate_max_len.unwrap_or(defaults.length.end),
+            syllables: args.syllables.clone(),
+            // Limits
+            max_attempts: match args.generate_max_attempts {
+                None => defaults.max_attempts,
+                Some(0) => None,
+                Some(max) => Some(max),
+            },
+            timeout: args.generate_timeout.map(Duration::from_secs_f64),
+            seed: args.seed,
+        };
 
         if count > 0 {
             if args.no_color {
-                println!(
+                outln!(
+                    human,
                     "Randomly generated word{s}:",
                     s = if count == 1 { "" } else { "s" }
                 );
             } else {
-                println!(
+                outln!(
+                    human,
                     "\x1b[34mRandomly generated word{s}:\x1b[0m",
                     s = if count == 1 { "" } else { "s" }
                 );
             }
 
             // Generate words
-            let words = scheme
-                .generate(count, length)
-                .map_err(|err| err.to_string())
-                .expect("Could not generate words");
+            // If generation stops early, display words that were generated, then error
+            let (words, error) = match scheme.generate_with(count, &options) {
+                Ok(words) => (words, None),
+                Err(err) => match &err {
+                    Error::GenerationExhausted { words, .. } => (words.clone(), Some(err)),
+                    _ => (Vec::new(), Some(err)),
+                },
+            };
 
             // Print words
             for word in words {
                 if args.no_color {
-                    println!(" - {}", word);
+                    outln!(human, " - {}", word);
                 } else {
-                    println!(" \x1b[36m- \x1b[0;3m{}\x1b[0m", word);
+                    outln!(human, " \x1b[36m- \x1b[0;3m{}\x1b[0m", word);
                 }
             }
+
+            if let Some(err) = error {
+                print_error(err, args.no_color);
+                status.fail(Status::GenerationFailed);
+            }
+        }
+    }
+
+    // List every valid word, if CLI arg given
+    if let Some(max_len) = args.enumerate {
+        if args.no_color {
+            outln!(human, "Valid words, up to length {max_len}:");
+        } else {
+            outln!(human, "\x1b[34mValid words, up to length {max_len}:\x1b[0m");
+        }
+
+        let words = match scheme.enumerate(max_len) {
+            Ok(words) => words,
+            Err(err) => {
+                print_error(err, args.no_color);
+                status.fail(Status::GenerationFailed);
+                return (status, Some(results));
+            }
+        };
+
+        // Amount of words of each length
+        let mut counts = BTreeMap::new();
+
+        // Print words as they are found
+        for word in words {
+            *counts.entry(word.chars().count()).or_insert(0) += 1;
+
+            if args.no_color {
+                outln!(human, " - {}", word);
+            } else {
+                outln!(human, " \x1b[36m- \x1b[0;3m{}\x1b[0m", word);
+            }
+        }
+
+        // Print counts
+        for (length, count) in &counts {
+            if args.no_color {
+                outln!(human, "Length {length}: {count}");
+            } else {
+                outln!(human, "\x1b[34mLength {length}:\x1b[0m {count}");
+            }
+        }
+        let total: usize = counts.values().sum();
+        if args.no_color {
+            outln!(human, "Total: {total}");
+        } else {
+            outln!(human, "\x1b[34;1mTotal:\x1b[0m {total}");
+        }
+    }
+
+    // Count valid words of each length, if CLI arg given
+    if let Some(length) = &args.capacity {
+        let options = CapacityOptions {
+            seed: args.seed,
+            ..Default::default()
+        };
+        let capacities = match scheme.capacity(length.clone(), &options) {
+            Ok(capacities) => capacities,
+            Err(err) => {
+                print_error(err, args.no_color);
+                status.fail(Status::GenerationFailed);
+                return (status, Some(results));
+            }
+        };
+
+        if args.no_color {
+            outln!(human, "Length  Candidates      Valid words");
+        } else {
+            outln!(human, "\x1b[34mLength  Candidates      Valid words\x1b[0m");
+        }
+
+        for capacity in capacities {
+            let count = match capacity.count {
+                CapacityCount::Exact(count) => format!("{count}"),
+                CapacityCount::Estimate {
+                    count, low, high, ..
+                } => format!(
+                    "~{} (95% CI {} - {})",
+                    format_count(count),
+                    format_count(low),
+                    format_count(high),
+                ),
+            };
+
+            outln!(
+                human,
+                "{:>6}  {:<14}  {}",
+                capacity.length,
+                format_count(capacity.candidates),
+                count
+            );
+        }
+    }
+
+    (status, Some(results))
+}
+
+/// Display summary of every file, with problems of each file
+fn summary(outcomes: &[(&str, Status, Option<Results>)], no_color: bool) {
+    let tests: usize = outcomes
+        .iter()
+        .filter_map(|(_, _, results)| results.as_ref())
+        .map(Results::test_count)
+        .sum();
+    let fails: u32 = outcomes
+        .iter()
+        .filter_map(|(_, _, results)| results.as_ref())
+        .map(|results| results.fail_count)
+        .sum();
+
+    if no_color {
+        println!("\nSummary of {} files:", outcomes.len());
+    } else {
+        println!("\n\x1b[34;1mSummary of {} files:\x1b[0m", outcomes.len());
+    }
+
+    let mut failed_files = 0;
+    for (path, status, results) in outcomes {
+        let problem = match status {
+            Status::Success => continue,
+            Status::TestsFailed => match results {
+                Some(results) if results.fail_count > 0 => format!(
+                    "{fails} of {tests} tests failed",
+                    fails = results.fail_count,
+                    tests = results.test_count(),
+                ),
+                Some(_) => "No tests ran".to_string(),
+                // Word list was checked instead of running tests
+                None => "Some words are invalid".to_string(),
+            },
+            Status::ParseError => "Could not be parsed".to_string(),
+            Status::IoError => "Could not be read or written".to_string(),
+            Status::GenerationFailed => "Words could not be generated".to_string(),
+            Status::LintWarnings => "Lint found some warnings".to_string(),
+            Status::NotFormatted => "Not formatted".to_string(),
+        };
+        failed_files += 1;
+
+        if no_color {
+            println!(" - `{path}`: {problem}");
+        } else {
+            println!(" \x1b[36m- \x1b[0m`{path}`: \x1b[31m{problem}\x1b[0m");
+        }
+    }
+
+    let mut total = if failed_files == 0 {
+        format!("All {} files passed", outcomes.len())
+    } else {
+        format!("{failed_files} of {} files failed", outcomes.len())
+    };
+    if tests > 0 && failed_files == 0 {
+        total += &format!(", with {tests} tests");
+    } else if tests > 0 {
+        total += &format!(", with {fails} of {tests} tests failed");
+    }
+    if no_color {
+        println!("{total}");
+    } else if failed_files == 0 {
+        println!("\x1b[32;1;3m{total}\x1b[0m");
+    } else {
+        println!("\x1b[31;1;3m{total}\x1b[0m");
+    }
+}
+
+/// Display warnings of possible mistakes in file
+fn lint(scheme: &Phonet, file: &str, no_color: bool) -> Status {
+    let warnings = scheme.lint(file);
+
+    for warning in &warnings {
+        println!("{}", warning.render(file, no_color));
+    }
+
+    if warnings.is_empty() {
+        if no_color {
+            println!("No warnings!");
+        } else {
+            println!("\x1b[32;1;3mNo warnings!\x1b[0m");
+        }
+        return Status::Success;
+    }
+
+    let count = format!(
+        "{count} warning{s}",
+        count = warnings.len(),
+        s = if warnings.len() == 1 { "" } else { "s" },
+    );
+    if no_color {
+        println!("{count}");
+    } else {
+        println!("\x1b[33;1;3m{count}\x1b[0m");
+    }
+    Status::LintWarnings
+}
+
+/// Format file and save, or check that file is formatted
+fn fmt(path: &str, file: &str, check: bool, no_color: bool) -> Status {
+    let formatted = format_file(file);
+
+    let message = if formatted == file {
+        format!("File `{path}` is formatted")
+    } else if check {
+        print_error(
+            format!("File `{path}` is not formatted. Format with `phonet fmt`"),
+            no_color,
+        );
+        return Status::NotFormatted;
+    } else {
+        if let Err(err) = fs::write(path, &formatted) {
+            print_error(format!("Could not write file `{path}`: {err}"), no_color);
+            return Status::IoError;
+        }
+        format!("Formatted file `{path}`")
+    };
+
+    if no_color {
+        println!("{message}");
+    } else {
+        println!("\x1b[32;1;3m{message}\x1b[0m");
+    }
+    Status::Success
+}
+
+/// Get paths of files to run, from files, directories, and glob patterns
+///
+/// Directories are searched recursively for `.phonet` files, and files called `phonet`. Minified
+/// files are skipped. Each file is only given once
+fn find_files(patterns: &[String]) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+
+    for pattern in patterns {
+        let path = Path::new(pattern);
+
+        if path.is_dir() {
+            let count = paths.len();
+            find_in_dir(path, &mut paths)
+                .map_err(|err| format!("Could not read directory `{pattern}`: {err}"))?;
+            if paths.len() == count {
+                return Err(format!("No phonet files in directory `{pattern}`"));
+            }
+        } else if pattern.contains(['*', '?', '[']) {
+            let matches =
+                glob::glob(pattern).map_err(|err| format!("Invalid pattern `{pattern}`: {err}"))?;
+
+            let count = paths.len();
+            for entry in matches {
+                let path = entry.map_err(|err| format!("Could not read `{pattern}`: {err}"))?;
+                if path.is_dir() {
+                    find_in_dir(&path, &mut paths).map_err(|err| {
+                        format!("Could not read directory `{}`: {err}", path.display())
+                    })?;
+                } else {
+                    paths.push(path.display().to_string());
+                }
+            }
+            if paths.len() == count {
+                return Err(format!("No files match pattern `{pattern}`"));
+            }
+        } else {
+            // File is read later, to display error for that file
+            paths.push(pattern.clone());
+        }
+    }
+
+    let mut found = HashSet::new();
+    paths.retain(|path| found.insert(path.clone()));
+    Ok(paths)
+}
+
+/// Add paths of phonet files in directory, and subdirectories, in alphabetical order
+fn find_in_dir(dir: &Path, paths: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            find_in_dir(&path, paths)?;
+            continue;
+        }
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        if name == "phonet" || (name.ends_with(".phonet") && !name.ends_with(".min.phonet")) {
+            paths.push(path.display().to_string());
         }
     }
 
     Ok(())
 }
 
+/// Display whether each word of word list is valid, with reason if invalid
+///
+/// If `split` is given, valid and invalid words are saved to files in directory, with names starting
+/// with prefix
+fn check_words(
+    scheme: &Phonet,
+    words: &[String],
+    options: &BatchOptions,
+    split: Option<(&Path, &str)>,
+    display_level: DisplayLevel,
+    no_color: bool,
+) -> Status {
+    let batch = scheme.validate_all_with(words, options);
+
+    let max_word_len = words
+        .iter()
+        .map(|word| word.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut valid = String::new();
+    let mut invalid = String::new();
+
+    for (word, violation) in words.iter().zip(&batch.list) {
+        let space = " ".repeat(max_word_len - word.chars().count());
+
+        let Some(violation) = violation else {
+            valid += &format!("{word}\n");
+
+            if matches!(display_level, DisplayLevel::ShowAll) {
+                if no_color {
+                    println!(" ✔ {word}{space}  valid");
+                } else {
+                    println!("  \x1b[36m✔\x1b[0m {word}{space}  \x1b[1;32mvalid\x1b[0m");
+                }
+            }
+            continue;
+        };
+
+        let reason = violation.reason.as_deref().unwrap_or("No reason given");
+        invalid += &format!("{word}\t{reason}\n");
+
+        if !matches!(display_level, DisplayLevel::HideAll) {
+            if no_color {
+                println!(" ✗ {word}{space}  INVALID {reason}");
+            } else {
+                println!(
+                    "  \x1b[35m✗\x1b[0m {word}{space}  \x1b[1;31mINVALID \x1b[0;3;1m{reason}\x1b[0m"
+                );
+            }
+        }
+    }
+
+    // Save valid and invalid words
+    if let Some((dir, prefix)) = split {
+        let result = fs::create_dir_all(dir).and_then(|_| {
+            fs::write(dir.join(format!("{prefix}valid.txt")), &valid)?;
+            fs::write(dir.join(format!("{prefix}invalid.txt")), &invalid)
+        });
+        if let Err(err) = result {
+            print_error(
+                format!("Could not write word lists to `{}`: {err}", dir.display()),
+                no_color,
+            );
+            return Status::IoError;
+        }
+    }
+
+    let total = format!(
+        "{valid} of {count} word{s} valid",
+        valid = batch.valid_count(),
+        count = words.len(),
+        s = if words.len() == 1 { " is" } else { "s are" },
+    );
+    let throughput = format!(
+        "Checked in {time:.2?}, {speed:.0} words per second, with {threads} thread{s}",
+        time = batch.elapsed,
+        speed = batch.words_per_second(),
+        threads = batch.threads,
+        s = if batch.threads == 1 { "" } else { "s" },
+    );
+    if no_color {
+        println!("{total}\n{throughput}");
+    } else if batch.invalid_count() == 0 {
+        println!("\x1b[32;1;3m{total}\x1b[0m\n\x1b[2m{throughput}\x1b[0m");
+    } else {
+        println!("\x1b[31;1;3m{total}\x1b[0m\n\x1b[2m{throughput}\x1b[0m");
+    }
+
+    if batch.invalid_count() > 0 {
+        Status::TestsFailed
+    } else {
+        Status::Success
+    }
+}
+
+/// Display error to standard error
+fn print_error(err: impl Display, no_color: bool) {
+    if no_color {
+        eprintln!("{err}");
+    } else {
+        eprintln!("\x1b[31m{err}\x1b[0m");
+    }
+}
+
+/// Print machine-readable output to standard output, ending with a newline
+fn print_output(output: &str) {
+    if output.ends_with('\n') {
+        print!("{output}");
+    } else {
+        println!("{output}");
+    }
+}
+
+/// Format large amount, using scientific notation if very large
+fn format_count(count: f64) -> String {
+    if count < 1e12 {
+        format!("{count:.0}")
+    } else {
+        format!("{count:.3e}")
+    }
+}
+
 /// Adds '.min' to filename, before last file extension
 ///
 /// Returns empty string if filename is empty
@@ -124,4 +783,26 @@ mod tests {
         assert_eq!(get_min_filename("myfile.phonet"), "myfile.min.phonet");
         assert_eq!(get_min_filename("one.two.phonet"), "one.two.min.phonet");
     }
+
+    #[test]
+    fn find_files_works() {
+        let files = find_files(&[
+            "examples/t*.phonet".to_string(),
+            "examples".to_string(),
+            "missing.phonet".to_string(),
+        ]);
+        assert_eq!(
+            files,
+            Ok(vec![
+                "examples/test.phonet".to_string(),
+                "examples/tokipona.phonet".to_string(),
+                "examples/esperanto.phonet".to_string(),
+                "examples/example.phonet".to_string(),
+                "examples/ivalingo.phonet".to_string(),
+                "missing.phonet".to_string(),
+            ])
+        );
+
+        assert!(find_files(&["examples/*.txt".to_string()]).is_err());
+    }
 }
@@ -0,0 +1,182 @@
+use regex::RegexSet;
+
+use crate::types::Rule;
+
+/// Rules combined into a single matcher, for faster validation
+///
+/// Rules which need no fancy features, such as look-around or backreferences, are matched together
+/// in a single pass with a `RegexSet`. Other rules are matched one at a time with `fancy_regex`,
+/// only when they are needed
+#[derive(Clone, Debug)]
+pub(crate) struct Matcher {
+    /// Rules which need no fancy features
+    set: RegexSet,
+    /// Index in `set` of each rule, or `None` if rule needs fancy features
+    slots: Vec<Option<usize>>,
+}
+
+impl Matcher {
+    /// Combine rules into matcher
+    pub fn new(rules: &[Rule]) -> Self {
+        let mut patterns = Vec::new();
+        let slots = rules
+            .iter()
+            .map(|rule| {
+                let pattern = rule.pattern.as_str();
+                // Pattern needs fancy features if standard regex does not support it
+                regex::Regex::new(pattern).ok().map(|_| {
+                    patterns.push(pattern);
+                    patterns.len() - 1
+                })
+            })
+            .collect();
+
+        match RegexSet::new(&patterns) {
+            Ok(set) => Self { set, slots },
+            // Set is too large, so every rule is matched one at a time
+            Err(_) => Self {
+                set: RegexSet::empty(),
+                slots: vec![None; rules.len()],
+            },
+        }
+    }
+
+    /// Get amount of rules which are matched together
+    pub fn combined_count(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Get index of first rule that word does not follow
+    pub fn first_failing(&self, word: &str, rules: &[Rule]) -> Option<usize> {
+        self.matches(word, rules)
+            .zip(rules)
+            .position(|(is_match, rule)| !follows_rule(rule, is_match))
+    }
+
+    /// Get indexes of every rule that word does not follow, in order
+    pub fn all_failing(&self, word: &str, rules: &[Rule]) -> Vec<usize> {
+        self.matches(word, rules)
+            .zip(rules)
+            .enumerate()
+            .filter(|(_, (is_match, rule))| !follows_rule(rule, *is_match))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Get whether pattern of each rule matches word, in order of rules
+    ///
+    /// Rules which need fancy features are only matched when reached
+    ///
+    /// If rules were changed since matcher was created, every rule is matched one at a time
+    fn matches<'a>(&'a self, word: &'a str, rules: &'a [Rule]) -> impl Iterator<Item = bool> + 'a {
+        let set = (!self.set.is_empty() && self.is_fresh(rules)).then(|| self.set.matches(word));
+
+        rules.iter().enumerate().map(move |(index, rule)| {
+            match (&set, self.slots.get(index).copied().flatten()) {
+                (Some(set), Some(slot)) => set.matched(slot),
+                _ => rule
+                    .pattern
+                    .is_match(word)
+                    .expect("Failed checking regex match. This error should NEVER APPEAR!"),
+            }
+        })
+    }
+
+    /// Returns `true` if each rule in `set` has the same position and pattern as when matcher was
+    /// created
+    fn is_fresh(&self, rules: &[Rule]) -> bool {
+        let patterns = self.set.patterns();
+
+        self.slots.len() == rules.len()
+            && self
+                .slots
+                .iter()
+                .zip(rules)
+                .all(|(slot, rule)| slot.is_none_or(|slot| patterns[slot] == rule.pattern.as_str()))
+    }
+}
+
+/// Returns `true` if word follows rule, with whether pattern of rule matches word
+fn follows_rule(rule: &Rule, is_match: bool) -> bool {
+    // Check if rule matches, and whether match signifies invalid
+    !(rule.intent ^ is_match)
+}
+
+#[cfg(test)]
+mod tests {
+    use fancy_regex::Regex;
+
+    use super::*;
+
+    /// Create rule from intent and pattern
+    fn rule(intent: bool, pattern: &str) -> Rule {
+        Rule {
+            intent,
+            pattern: Regex::new(pattern).expect("Failed to create regex"),
+            reason_ref: None,
+            line: 0,
+            file: None,
+        }
+    }
+
+    #[test]
+    fn matcher_works() {
+        let rules = vec![
+            rule(true, "^[ptkaeiou]+$"),
+            rule(false, "(.)\\1"),
+            rule(false, "k(?=i)"),
+            rule(true, "^([ptk][aeiou])+$"),
+            rule(false, "(?<=a)t"),
+            rule(false, "uu"),
+        ];
+        let matcher = Matcher::new(&rules);
+        assert_eq!(matcher.combined_count(), 3);
+
+        // Same as matching each rule in order
+        for word in [
+            "", "taka", "tata", "taki", "kaat", "atka", "tuuk", "xyz", "kiki",
+        ] {
+            let expected: Vec<_> = rules
+                .iter()
+                .enumerate()
+                .filter(|(_, rule)| rule.intent != rule.pattern.is_match(word).unwrap())
+                .map(|(index, _)| index)
+                .collect();
+
+            assert_eq!(matcher.all_failing(word, &rules), expected, "{word}");
+            assert_eq!(
+                matcher.first_failing(word, &rules),
+                expected.first().copied(),
+                "{word}"
+            );
+        }
+
+        // Rules changed after matcher was created
+        assert_eq!(matcher.first_failing("uu", &rules[5..]), Some(0));
+
+        // Rules reordered after matcher was created
+        let mut rules = rules;
+        rules.swap(0, 5);
+        for word in ["taka", "tuuk", "xyz"] {
+            let expected: Vec<_> = rules
+                .iter()
+                .enumerate()
+                .filter(|(_, rule)| rule.intent != rule.pattern.is_match(word).unwrap())
+                .map(|(index, _)| index)
+                .collect();
+
+            assert_eq!(matcher.all_failing(word, &rules), expected, "{word}");
+        }
+    }
+
+    #[test]
+    fn reordered_rules_are_matched() {
+        let mut scheme = crate::Phonet::parse("$_ = [abc]; @ R1; + ^ <_>+ $; @ R2; ! aa")
+            .expect("Failed to parse");
+        scheme.rules.swap(0, 1);
+
+        assert!(scheme.first_violation("c").is_none());
+        let violation = scheme.first_violation("d").expect("Word should be invalid");
+        assert_eq!(violation.reason.as_deref(), Some("R1"));
+    }
+}
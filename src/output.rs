@@ -0,0 +1,289 @@
+use crate::{
+    types::{OutputFormat, TestResult},
+    FailReason, Results,
+};
+
+impl Results {
+    /// Format results as machine-readable text
+    ///
+    /// `name` is used as the name of the test suite, such as the file name
+    ///
+    /// Returns `None` for `OutputFormat::Human`, which should use `Results::display` instead
+    pub fn format(&self, format: OutputFormat, name: &str) -> Option<String> {
+        Some(match format {
+            OutputFormat::Human => return None,
+            OutputFormat::Json => self.to_json(),
+            OutputFormat::Tap => self.to_tap(),
+            OutputFormat::Junit => self.to_junit(name),
+        })
+    }
+
+    /// Format results as JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .expect("Failed to serialize results. This error should NEVER APPEAR!")
+    }
+
+    /// Format results with Test Anything Protocol (TAP) version 13
+    ///
+    /// Notes are included as comments
+    pub fn to_tap(&self) -> String {
+        format!(
+            "TAP version 13\n1..{}\n{}",
+            self.test_count(),
+            self.tap_lines(&mut 0)
+        )
+    }
+
+    /// Format each test and note with Test Anything Protocol, without version and plan
+    ///
+    /// Tests are numbered after `number`, which is set to the number of the last test
+    fn tap_lines(&self, number: &mut usize) -> String {
+        let mut output = String::new();
+
+        for item in &self.list {
+            let Some(test) = TestInfo::from(item) else {
+                if let TestResult::Note(note) = item {
+                    output += &format!("# {note}\n");
+                }
+                continue;
+            };
+            *number += 1;
+
+            output += &format!(
+                "{status} {number} - {name}\n",
+                status = if test.pass { "ok" } else { "not ok" },
+                name = test.name(),
+            );
+
+            // Reasons as YAML block
+            if !test.pass {
+                output += "  ---\n  reasons:\n";
+                for reason in &test.reasons {
+                    output += &format!("    - {}\n", yaml_string(reason));
+                }
+                output += "  ...\n";
+            }
+        }
+
+        output
+    }
+
+    /// Format results as JUnit XML
+    ///
+    /// Tests are grouped by the most recent note before them
+    pub fn to_junit(&self, name: &str) -> String {
+        format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<testsuites tests=\"{tests}\" failures=\"{failures}\">\n",
+                "{suite}",
+                "</testsuites>\n",
+            ),
+            tests = self.test_count(),
+            failures = self.fail_count,
+            suite = self.junit_suite(name),
+        )
+    }
+
+    /// Format results as a JUnit XML test suite
+    fn junit_suite(&self, name: &str) -> String {
+        let mut cases = String::new();
+        let mut group = name.to_string();
+
+        for item in &self.list {
+            let Some(test) = TestInfo::from(item) else {
+                if let TestResult::Note(note) = item {
+                    group = format!("{name}.{note}");
+                }
+                continue;
+            };
+
+            let attributes = format!(
+                r#"name="{}" classname="{}""#,
+                xml_escape(&test.name()),
+                xml_escape(&group),
+            );
+
+            if test.pass {
+                cases += &format!("    <testcase {attributes}/>\n");
+            } else {
+                cases += &format!(
+                    "    <testcase {attributes}>\n      <failure message=\"{message}\">{body}</failure>\n    </testcase>\n",
+                    message = xml_escape(test.reasons.first().map(String::as_str).unwrap_or("")),
+                    body = xml_escape(&test.reasons.join("\n")),
+                );
+            }
+        }
+
+        let tests = self.test_count();
+        let failures = self.fail_count;
+        let name = xml_escape(name);
+
+        format!(
+            concat!(
+                "  <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\">\n",
+                "{cases}",
+                "  </testsuite>\n",
+            ),
+            tests = tests,
+            failures = failures,
+            name = name,
+            cases = cases,
+        )
+    }
+}
+
+/// Format results of multiple files as machine-readable text
+///
+/// Each item is the name of a file, such as the path, and the results of that file. JSON is an array
+/// of results, each with a `file` field. TAP has a comment before the tests of each file, and JUnit
+/// has a test suite for each file
+///
+/// Returns `None` for `OutputFormat::Human`, which should use `Results::display` for each file
+pub fn format_files(files: &[(&str, &Results)], format: OutputFormat) -> Option<String> {
+    let tests: usize = files.iter().map(|(_, results)| results.test_count()).sum();
+    let failures: u32 = files.iter().map(|(_, results)| results.fail_count).sum();
+
+    Some(match format {
+        OutputFormat::Human => return None,
+
+        OutputFormat::Json => {
+            let files: Vec<_> = files
+                .iter()
+                .map(|(name, results)| {
+                    let mut value = serde_json::to_value(results)
+                        .expect("Failed to serialize results. This error should NEVER APPEAR!");
+                    if let Some(object) = value.as_object_mut() {
+                        object.insert("file".to_string(), (*name).into());
+                    }
+                    value
+                })
+                .collect();
+
+            serde_json::to_string_pretty(&files)
+                .expect("Failed to serialize results. This error should NEVER APPEAR!")
+        }
+
+        OutputFormat::Tap => {
+            let mut output = format!("TAP version 13\n1..{tests}\n");
+            let mut number = 0;
+            for (name, results) in files {
+                output += &format!("# File {name}\n");
+                output += &results.tap_lines(&mut number);
+            }
+            output
+        }
+
+        OutputFormat::Junit => format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<testsuites tests=\"{tests}\" failures=\"{failures}\">\n",
+                "{suites}",
+                "</testsuites>\n",
+            ),
+            tests = tests,
+            failures = failures,
+            suites = files
+                .iter()
+                .map(|(name, results)| results.junit_suite(name))
+                .collect::<String>(),
+        ),
+    })
+}
+
+/// Common information of `TestResult::Test` and `TestResult::Detailed`
+struct TestInfo<'a> {
+    intent: bool,
+    word: &'a str,
+    pass: bool,
+    /// Reasons for failure, empty if passed
+    reasons: Vec<String>,
+}
+
+impl<'a> TestInfo<'a> {
+    /// Get information of test, or `None` for note
+    fn from(item: &'a TestResult) -> Option<Self> {
+        Some(match item {
+            TestResult::Note(_) => return None,
+
+            TestResult::Test {
+                intent,
+                word,
+                pass,
+                reason,
+                ..
+            } => TestInfo {
+                intent: *intent,
+                word,
+                pass: *pass,
+                reasons: match reason {
+                    FailReason::Passed => vec![],
+                    FailReason::NoReasonGiven => vec!["No reason given".to_string()],
+                    FailReason::ShouldBeInvalid => vec![SHOULD_BE_INVALID.to_string()],
+                    FailReason::Custom(reason) => vec![reason.to_string()],
+                },
+            },
+
+            TestResult::Detailed {
+                intent,
+                word,
+                pass,
+                violations,
+            } => TestInfo {
+                intent: *intent,
+                word,
+                pass: *pass,
+                reasons: if *pass {
+                    vec![]
+                } else if violations.is_empty() {
+                    vec![SHOULD_BE_INVALID.to_string()]
+                } else {
+                    violations
+                        .iter()
+                        .map(|violation| {
+                            format!(
+                                "{reason} ({location})",
+                                reason = violation.reason.as_deref().unwrap_or("No reason given"),
+                                location = violation.location(),
+                            )
+                        })
+                        .collect()
+                },
+            },
+        })
+    }
+
+    /// Name of test, using test syntax
+    fn name(&self) -> String {
+        format!("?{} {}", if self.intent { '+' } else { '!' }, self.word)
+    }
+}
+
+/// Reason for failure, for negative test that was valid
+const SHOULD_BE_INVALID: &str = "Valid, but should be invalid";
+
+/// Escape special characters for XML attribute or text
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Quote string for YAML, using JSON string syntax (which is valid YAML)
+fn yaml_string(s: &str) -> String {
+    serde_json::to_string(s).expect("Failed to serialize string. This error should NEVER APPEAR!")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_escape_works() {
+        assert_eq!(xml_escape("a<b>&\"c'"), "a&lt;b&gt;&amp;&quot;c&apos;");
+        assert_eq!(xml_escape("abc"), "abc");
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use super::{replace_angle_brackets, substitute_classes};
+use crate::types::{Classes, Error, Span};
+
+/// Substitute classes used in values of every class
+///
+/// `positions` holds the position of each character of each class value
+///
+/// Errors are shown in the definition of the class they are in, even if found through another class.
+/// Classes with errors, or which use a class with an error, are left out, and each error is only
+/// added once
+///
+/// Classes which use themselves, directly or through other classes, give an error with the path of
+/// the cycle
+pub fn resolve_classes(
+    raw_classes: &Classes,
+    positions: &HashMap<String, Vec<Span>>,
+    errors: &mut Vec<Error>,
+) -> Classes {
+    let mut resolver = Resolver {
+        raw_classes,
+        positions,
+        classes: Classes::new(),
+        visited: HashSet::new(),
+        path: Vec::new(),
+        errors,
+    };
+
+    // Resolve in order of definition, so errors are in order
+    let mut names: Vec<&String> = raw_classes.keys().collect();
+    names.sort_by_key(|name| {
+        positions
+            .get(*name)
+            .and_then(|positions| positions.first())
+            .map(|span| (span.line, span.column))
+    });
+
+    for name in names {
+        resolver.resolve(name);
+    }
+
+    resolver.classes
+}
+
+/// Holds classes while resolving
+struct Resolver<'a> {
+    /// Classes, before substituting other classes
+    raw_classes: &'a Classes,
+    /// Position of each character of class values
+    positions: &'a HashMap<String, Vec<Span>>,
+    /// Classes which were resolved without error
+    classes: Classes,
+    /// Classes which were already resolved, with or without error
+    visited: HashSet<&'a str>,
+    /// Classes currently being resolved, each used by the previous
+    path: Vec<&'a str>,
+    errors: &'a mut Vec<Error>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Resolve class, after resolving every class used in its value
+    fn resolve(&mut self, name: &'a str) {
+        if !self.visited.insert(name) {
+            return;
+        }
+        let value = &self.raw_classes[name];
+        let positions = self.positions.get(name).map(Vec::as_slice).unwrap_or(&[]);
+
+        // Resolve classes used in value first
+        self.path.push(name);
+        for (used, range) in class_references(value) {
+            let Some((used, _)) = self.raw_classes.get_key_value(&used) else {
+                continue;
+            };
+
+            // Class is already being resolved
+            if let Some(start) = self.path.iter().position(|name| name == used) {
+                self.circular(start, Span::locate(positions, range));
+                continue;
+            }
+
+            self.resolve(used);
+        }
+        self.path.pop();
+
+        match substitute_classes(value, &self.classes, positions) {
+            Ok(value) => {
+                self.classes.insert(name.to_string(), value);
+            }
+            // Class with error was already reported
+            Err(Error::ClassNotFound { name, .. }) if self.raw_classes.contains_key(&name) => (),
+            Err(err) => self.errors.push(err),
+        }
+    }
+
+    /// Add error for cycle of classes, from index in path to current class
+    ///
+    /// `span` is the position of the class name which completes the cycle
+    fn circular(&mut self, start: usize, span: Span) {
+        let cycle = &self.path[start..];
+
+        // Line of definition of each class in cycle
+        let lines = cycle
+            .iter()
+            .map(|name| {
+                self.positions
+                    .get(*name)
+                    .and_then(|positions| positions.first())
+                    .map(|span| span.line)
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut path: Vec<String> = cycle.iter().map(|name| name.to_string()).collect();
+        path.push(cycle[0].to_string());
+
+        self.errors.push(Error::CircularClass { path, lines, span });
+    }
+}
+
+/// Get names of classes used in pattern, with range of characters of each
+pub fn class_references(pattern: &str) -> Vec<(String, Range<usize>)> {
+    let mut references = Vec::new();
+    // Build class name, with index of opening bracket
+    let mut name_build: Option<(String, usize)> = None;
+
+    for (i, ch) in replace_angle_brackets(pattern).chars().enumerate() {
+        match ch {
+            '⟨' => name_build = Some((String::new(), i)),
+
+            '⟩' => {
+                if let Some((name, start)) = name_build.take() {
+                    references.push((name, start..i + 1));
+                }
+            }
+
+            _ => {
+                if let Some((name, _)) = &mut name_build {
+                    name.push(ch);
+                }
+            }
+        }
+    }
+
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_references_works() {
+        assert_eq!(
+            class_references("(?:<C><Vowel>)"),
+            vec![("C".to_string(), 3..6), ("Vowel".to_string(), 6..13)]
+        );
+        assert_eq!(
+            class_references("(?<=a)<C>?"),
+            vec![("C".to_string(), 6..9)]
+        );
+        assert!(class_references("[ptk]").is_empty());
+    }
+}
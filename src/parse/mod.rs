@@ -1,342 +1,859 @@
+/// Resolve classes used in other classes
+mod classes;
 /// Split file into statements
 mod statements;
+/// Parse syllable templates
+mod syllables;
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fs, mem,
+    path::{Path, PathBuf},
+};
 
 use fancy_regex::Regex;
 use fancy_regex_macro::regex;
 
 use crate::{
+    matcher::Matcher,
+    run::{match_span, RunOptions, Violation},
+    syntax::{escape_multiline, normalize_brackets},
     types::{
         Classes,
         Error::{self, *},
-        Rule, TestDefinition,
+        Rule, Span, Syllable, TestDefinition, Weights,
     },
     Results,
 };
-use statements::split_statements;
+pub(crate) use classes::class_references;
+use classes::resolve_classes;
+pub(crate) use statements::{split_statements, Statement};
+use syllables::parse_syllable;
 
 struct RawRule {
     pub intent: bool,
     pub pattern: String,
     pub reason_ref: Option<usize>,
     pub line: usize,
+    /// Path of file which rule was defined in, if included
+    pub file: Option<String>,
+    /// Position of each character of pattern
+    pub positions: Vec<Span>,
 }
 
-/// Holds data for minify
+/// Minified statement, for minify
 #[derive(Debug)]
-struct Mini {
-    /// Classes defined
-    classes: Vec<String>,
-    /// Rules defined
-    rules: Vec<String>,
-    /// Positive tests defined
-    tests_pos: Vec<String>,
-    /// Negative tests defined
-    tests_neg: Vec<String>,
+enum Mini {
+    /// Mode, class, weights, syllable template, or rule
+    Definition(String),
+    /// Test or note, which is left out without tests
+    Test(String),
+    /// Reason, which is also a note if noted
+    Reason { reason: String, noted: bool },
+}
+
+/// Holds definitions while parsing statements
+struct Builder {
+    /// Classes, before substituting other classes
+    raw_classes: Classes,
+    /// Position of each character of class values
+    class_positions: HashMap<String, Vec<Span>>,
+    weights: Weights,
+    /// Syllable templates, with position of template
+    syllables: Vec<(Syllable, Span)>,
+    tests: Vec<TestDefinition>,
+    rules: Vec<RawRule>,
+    reasons: Vec<String>,
+    /// Reason for following rules
+    reason_ref: Option<usize>,
+    /// Minified statements, in order of definition
+    mini: Vec<Mini>,
+    mode: Option<Mode>,
+    /// Directory which included files are relative to
+    dir: PathBuf,
+    /// Files being parsed, from outermost file, with path to display, to find circular includes
+    including: Vec<(PathBuf, String)>,
+    /// Files which were already included, which are not included again
+    included: HashSet<PathBuf>,
+    /// Whether file is included by another file, so tests, notes, and mode are left out
+    is_included: bool,
+    /// Classes defined by included files, which can be defined again
+    included_classes: HashSet<String>,
+    /// Weights defined by included files, which can be defined again
+    included_weights: HashSet<String>,
+    /// Errors in files included by last statement
+    include_errors: Vec<Error>,
 }
 
-impl Mini {
+impl Builder {
     /// Create empty struct
     pub fn new() -> Self {
-        Mini {
-            classes: Vec::new(),
+        Builder {
+            raw_classes: HashMap::new(),
+            class_positions: HashMap::new(),
+            weights: HashMap::new(),
+            syllables: Vec::new(),
+            tests: Vec::new(),
             rules: Vec::new(),
-            tests_pos: Vec::new(),
-            tests_neg: Vec::new(),
+            reasons: Vec::new(),
+            reason_ref: None,
+            mini: Vec::new(),
+            mode: None,
+            dir: PathBuf::new(),
+            including: Vec::new(),
+            included: HashSet::new(),
+            is_included: false,
+            included_classes: HashSet::new(),
+            included_weights: HashSet::new(),
+            include_errors: Vec::new(),
         }
     }
-}
-
-#[derive(Debug)]
-pub enum Mode {
-    Romanized,
-    Broad,
-    Narrow,
-}
 
-impl Default for Mode {
-    fn default() -> Self {
-        Self::Romanized
+    /// Parse file, returning scheme, and every error in order found
+    fn parse(mut self, file: &str) -> (Phonet, Vec<Error>) {
+        let mut errors = self.statements(file);
+        let scheme = self.build(&mut errors);
+        (scheme, errors)
     }
-}
 
-/// Scheme parsed from file
-///
-/// Holds rules and tests
-#[derive(Debug)]
-pub struct Phonet {
-    /// Defined rules
-    pub rules: Vec<Rule>,
-    /// Tests to run
-    pub tests: Vec<TestDefinition>,
-    /// Defined reasons values for rules
-    pub reasons: Vec<String>,
-    /// Classes
-    pub classes: Classes,
-    /// Mode - This is only semantical
-    pub mode: Mode,
-    /// Minified data
-    mini: Mini,
-}
+    /// Parse every statement of file, and add definitions
+    ///
+    /// Returns every error, in order found
+    fn statements(&mut self, file: &str) -> Vec<Error> {
+        let mut errors = Vec::new();
 
-impl Phonet {
-    /// Get count of tests in list
-    pub fn test_count(&self) -> usize {
-        self.tests
-            .iter()
-            .filter(|item| matches!(item, TestDefinition::Test { .. }))
-            .count()
+        for statement in split_statements(file) {
+            if let Err(err) = self.statement(&statement) {
+                errors.push(err);
+            }
+            errors.append(&mut self.include_errors);
+        }
+
+        errors
     }
 
-    /// Parse `Phonet` from string
-    pub fn parse(file: &str) -> Result<Phonet, Error> {
-        // Builders
-        let mut raw_classes: Classes = HashMap::new();
-        let mut tests: Vec<TestDefinition> = Vec::new();
-        let mut rules: Vec<RawRule> = Vec::new();
+    /// Parse a single statement, and add definitions
+    ///
+    /// Nothing after an error in statement is added
+    fn statement(&mut self, statement: &Statement) -> Result<(), Error> {
+        let line = statement.line;
+        let text = statement.text.trim();
 
-        let mut reasons = Vec::new();
-        let mut reason_ref: Option<usize> = None;
+        // Continue for blank
+        if text.is_empty() {
+            return Ok(());
+        }
 
-        // For minify
-        let mut mini = Mini::new();
+        // Position of whole statement
+        let whole = statement.span(text);
 
-        // Mode
-        let mut mode: Option<Mode> = None;
+        let mut chars = text.chars();
 
-        // Split file into statements
-        let statements = split_statements(file);
+        if let Some(first) = chars.next() {
+            match first {
+                // Comment
+                '#' => return Ok(()),
 
-        for (statement, line) in statements {
-            let statement = statement.trim();
+                // Tests, notes, and mode of included files are left out
+                '?' | '*' | '~' if self.is_included => return Ok(()),
 
-            // Continue for blank
-            if statement.is_empty() {
-                continue;
-            }
+                // Include file
+                '<' => {
+                    let path = chars.as_str().trim();
 
-            let mut chars = statement.chars();
+                    // Add include for minify
+                    self.mini.push(Mini::Definition(format!("<{path}")));
 
-            if let Some(first) = chars.next() {
-                match first {
-                    // Comment
-                    '#' => continue,
+                    self.include(path, whole)?;
+                }
 
-                    // Mode
-                    '~' => {
-                        if mode.is_some() {
-                            return Err(Error::ModeAlreadyDefined { line });
-                        }
+                // Mode
+                '~' => {
+                    if self.mode.is_some() {
+                        return Err(Error::ModeAlreadyDefined { span: whole });
+                    }
 
-                        // Remove spaces
-                        while chars.as_str().starts_with(' ') {
-                            chars.next();
-                        }
+                    // Add mode for minify
+                    self.mini.push(Mini::Definition(format!(
+                        "~{}",
+                        chars.as_str().replace(' ', "")
+                    )));
 
-                        // Select mode
-                        let next = chars.next();
-                        let last = chars.last();
-                        mode = match (next, last) {
-                            (Some('<'), Some('>')) => Some(Mode::Romanized),
-                            (Some('/'), Some('/')) => Some(Mode::Broad),
-                            (Some('['), Some(']')) => Some(Mode::Narrow),
+                    // Remove spaces
+                    while chars.as_str().starts_with(' ') {
+                        chars.next();
+                    }
 
-                            _ => return Err(Error::InvalidMode { line }),
-                        };
+                    // Select mode
+                    let next = chars.next();
+                    let last = chars.last();
+                    self.mode = match (next, last) {
+                        (Some('<'), Some('>')) => Some(Mode::Romanized),
+                        (Some('/'), Some('/')) => Some(Mode::Broad),
+                        (Some('['), Some(']')) => Some(Mode::Narrow),
+
+                        _ => return Err(Error::InvalidMode { span: whole }),
+                    };
+                }
+
+                // Class
+                '$' => {
+                    let mut split = chars.as_str().split('=');
+
+                    // Get name
+                    let (name, span) = match split.next() {
+                        Some(x) => (x.trim().to_string(), statement.span(x.trim())),
+                        None => return Err(Error::NoClassName { span: whole }),
+                    };
+
+                    // Check if name is valid
+                    if !regex!(r"^\w+$")
+                        .is_match(&name)
+                        .expect("Failed checking regex match. This error should NEVER APPEAR!")
+                    {
+                        return Err(Error::InvalidClassName { name, span });
                     }
 
-                    // Class
-                    '$' => {
-                        let mut split = chars.as_str().split('=');
+                    // Get value
+                    let value = match split.next() {
+                        Some(x) => x.trim(),
+                        None => return Err(Error::NoClassValue { name, span }),
+                    };
+                    let mut positions = positions_without_spaces(statement, value);
+
+                    // Check that class does not already exist, unless defined by included file
+                    if self.raw_classes.contains_key(&name) && !self.included_classes.remove(&name)
+                    {
+                        return Err(Error::ClassAlreadyExist { name, span });
+                    }
 
-                        // Get name
-                        let name = match split.next() {
-                            Some(x) => x.trim().to_string(),
-                            None => return Err(Error::NoClassName { line }),
-                        };
+                    // Add class for minify
+                    self.mini.push(Mini::Definition(format!(
+                        "${}={}",
+                        name,
+                        normalize_brackets(value).replace(' ', "")
+                    )));
+
+                    // Insert class
+                    // Wrap value in NON-CAPTURING GROUP (just in case)
+                    // This is non-capturing, for classes to work with back-references
+                    // otherwise classes would be inherently capturing, and count towards group index in back-reference
+                    self.raw_classes
+                        .insert(name.to_string(), format!("(?:{})", value.replace(' ', "")));
+
+                    // Group brackets are shown as whole value, for errors
+                    let whole_value = if positions.is_empty() {
+                        span
+                    } else {
+                        Span::covering(&positions)
+                    };
+                    positions.splice(0..0, [whole_value; 3]);
+                    positions.push(whole_value);
+                    self.class_positions.insert(name, positions);
+                }
 
-                        // Check if name is valid
-                        if !regex!(r"^\w+$")
-                            .is_match(&name)
-                            .expect("Failed checking regex match. This error should NEVER APPEAR!")
-                        {
-                            return Err(Error::InvalidClassName { name, line });
-                        }
+                // Weights
+                '%' => {
+                    // Add weights for minify
+                    let items: Vec<_> = chars.as_str().split_whitespace().collect();
+                    self.mini
+                        .push(Mini::Definition(format!("%{}", items.join(" "))));
+
+                    // Split at space
+                    for item in items {
+                        // Split segment and weight at last colon
+                        let Some((segment, weight)) = item.rsplit_once(':') else {
+                            return Err(InvalidWeight {
+                                value: item.to_string(),
+                                span: statement.span(item),
+                            });
+                        };
 
-                        // Get value
-                        let value = match split.next() {
-                            Some(x) => x.trim(),
-                            None => return Err(Error::NoClassValue { name, line }),
+                        // Weight must be a positive number or zero
+                        let weight = match weight.parse::<f64>() {
+                            Ok(x) if !segment.is_empty() && x.is_finite() && x >= 0.0 => x,
+                            _ => {
+                                return Err(InvalidWeight {
+                                    value: item.to_string(),
+                                    span: statement.span(item),
+                                })
+                            }
                         };
 
-                        // Check that class does not already exist
-                        if raw_classes.get(&name).is_some() {
-                            return Err(Error::ClassAlreadyExist { name, line });
+                        // Check that weight does not already exist, unless defined by included file
+                        if self.weights.contains_key(segment)
+                            && !self.included_weights.remove(segment)
+                        {
+                            return Err(WeightAlreadyDefined {
+                                segment: segment.to_string(),
+                                span: statement.span(segment),
+                            });
                         }
 
-                        // Add raw line
-                        mini.classes.push(format!(
-                            "${}={}",
-                            name,
-                            value.replace(' ', "").replace('⟨', "<").replace('⟩', ">")
-                        ));
-
-                        // Insert class
-                        // Wrap value in NON-CAPTURING GROUP (just in case)
-                        // This is non-capturing, for classes to work with back-references
-                        // otherwise classes would be inherently capturing, and count towards group index in back-reference
-                        raw_classes
-                            .insert(name.to_string(), format!("(?:{})", value.replace(' ', "")));
+                        self.weights.insert(segment.to_string(), weight);
                     }
+                }
 
-                    // Rule
-                    '+' | '!' => {
-                        // `+` for true, `!` for false
-                        let intent = first != '!';
-
-                        let pattern = chars.as_str().replace(' ', "");
+                // Syllable template
+                '=' => {
+                    let pattern = chars.as_str().replace(' ', "");
+                    let mut positions = positions_without_spaces(statement, chars.as_str());
+                    // Use position of operator for empty template
+                    if positions.is_empty() {
+                        positions.push(statement.span(&text[..1]));
+                    }
 
-                        // Add rule for minify
-                        mini.rules
-                            .push(first.to_string() + &pattern.replace('⟨', "<").replace('⟩', ">"));
+                    // Add syllable for minify
+                    self.mini.push(Mini::Definition(format!(
+                        "{first}{}",
+                        normalize_brackets(chars.as_str()).replace(' ', "")
+                    )));
 
-                        // Add rule
-                        rules.push(RawRule {
-                            intent,
-                            pattern,
-                            reason_ref,
+                    self.syllables.push((
+                        Syllable {
+                            parts: parse_syllable(&pattern, &positions)?,
                             line,
-                        });
+                        },
+                        Span::covering(&positions),
+                    ));
+                }
+
+                // Rule
+                '+' | '!' => {
+                    // `+` for true, `!` for false
+                    let intent = first != '!';
+
+                    let pattern = chars.as_str().replace(' ', "");
+                    let positions = positions_without_spaces(statement, chars.as_str());
+
+                    // Add rule for minify
+                    self.mini.push(Mini::Definition(format!(
+                        "{first}{}",
+                        normalize_brackets(chars.as_str()).replace(' ', "")
+                    )));
+
+                    // Add rule
+                    self.rules.push(RawRule {
+                        intent,
+                        pattern,
+                        reason_ref: self.reason_ref,
+                        line,
+                        file: None,
+                        positions,
+                    });
+                }
+
+                // Test
+                '?' => {
+                    // Remove spaces
+                    while chars.as_str().starts_with(' ') {
+                        chars.next();
                     }
 
-                    // Test
-                    '?' => {
-                        // Remove spaces
-                        while chars.as_str().starts_with(' ') {
-                            chars.next();
+                    // Check intent
+                    // `+` for true, `!` for false
+                    let rest = chars.as_str();
+                    let intent = match chars.next() {
+                        // Should be INVALID to pass
+                        Some('+') => true,
+                        // Should be VALID to pass
+                        Some('!') => false,
+
+                        // Unknown character
+                        Some(ch) => {
+                            return Err(UnknownIntentIdentifier {
+                                ch,
+                                span: statement.span(&rest[..ch.len_utf8()]),
+                            });
                         }
+                        // No character
+                        None => return Ok(()),
+                    };
+
+                    // Split at space
+                    let words: Vec<_> = chars.as_str().split_whitespace().collect();
+
+                    // Add tests for minify
+                    if !words.is_empty() {
+                        self.mini.push(Mini::Test(format!(
+                            "?{}{}",
+                            if intent { '+' } else { '!' },
+                            words.join(" ")
+                        )));
+                    }
 
-                        // Check intent
-                        // `+` for true, `!` for false
-                        let intent = match chars.next() {
-                            // Should be INVALID to pass
-                            Some('+') => true,
-                            // Should be VALID to pass
-                            Some('!') => false,
-
-                            // Unknown character
-                            Some(ch) => {
-                                return Err(UnknownIntentIdentifier { ch, line });
-                            }
-                            // No character
-                            None => continue,
-                        };
+                    for word in words {
+                        let word = word.trim().to_string();
 
-                        // Split at space
-                        let words = chars.as_str().split_whitespace();
-                        for word in words {
-                            let word = word.trim().to_string();
+                        // Add test
+                        if !word.is_empty() {
+                            self.tests.push(TestDefinition::Test { intent, word });
+                        }
+                    }
+                }
 
-                            // Add test for minify
-                            if intent {
-                                mini.tests_pos.push(word.clone());
-                            } else {
-                                mini.tests_neg.push(word.clone());
-                            }
+                // Reason
+                '@' => {
+                    // Remove spaces
+                    while chars.as_str().starts_with(' ') {
+                        chars.next();
+                    }
 
-                            // Add test
-                            if !word.is_empty() {
-                                tests.push(TestDefinition::Test { intent, word });
-                            }
+                    // Reason note
+                    let noted = chars.as_str().starts_with('*');
+                    if noted {
+                        chars.next();
+                        if !self.is_included {
+                            self.tests
+                                .push(TestDefinition::Note(chars.as_str().trim().to_string()));
                         }
                     }
 
-                    // Reason
-                    '@' => {
-                        // Remove spaces
-                        while chars.as_str().starts_with(' ') {
-                            chars.next();
-                        }
+                    // Add reason for minify
+                    let reason = chars.as_str().trim().to_string();
+                    self.mini.push(Mini::Reason {
+                        reason: reason.clone(),
+                        noted,
+                    });
 
-                        // Reason note
-                        if chars.as_str().starts_with('*') {
-                            chars.next();
-                            tests.push(TestDefinition::Note(chars.as_str().trim().to_string()));
-                        }
+                    // Add reason
+                    self.reasons.push(reason);
+                    self.reason_ref = Some(self.reasons.len() - 1);
+                }
 
-                        // Add reason
-                        reasons.push(chars.as_str().trim().to_string());
-                        reason_ref = Some(reasons.len() - 1);
-                    }
+                // Note
+                '*' => {
+                    let msg = chars.as_str().trim().to_string();
+                    if !msg.is_empty() {
+                        // Add note for minify
+                        self.mini.push(Mini::Test(format!("*{msg}")));
 
-                    // Note
-                    '*' => {
-                        let msg = chars.as_str().trim().to_string();
-                        if !msg.is_empty() {
-                            tests.push(TestDefinition::Note(msg));
-                        }
+                        self.tests.push(TestDefinition::Note(msg));
                     }
+                }
 
-                    // Unknown
-                    _ => return Err(UnknownLineOperator { ch: first, line }),
+                // Unknown
+                _ => {
+                    return Err(UnknownLineOperator {
+                        ch: first,
+                        span: statement.span(&text[..first.len_utf8()]),
+                    })
                 }
             }
         }
 
-        //TODO Add line number
-        let mut classes = Classes::new();
-        for (name, value) in &raw_classes {
-            classes.insert(
-                name.to_string(),
-                substitute_classes(value, &raw_classes, 0)?,
-            );
+        Ok(())
+    }
+
+    /// Include definitions of another file, relative to this file
+    ///
+    /// Errors in included file are added to `include_errors`, and nothing is included
+    fn include(&mut self, path: &str, span: Span) -> Result<(), Error> {
+        let path = self.dir.join(path);
+        let display = path.display().to_string();
+        let fail = |err: std::io::Error| IncludeFail {
+            path: display.clone(),
+            reason: err.to_string(),
+            span,
+        };
+
+        let canonical = path.canonicalize().map_err(fail)?;
+
+        // File is already being parsed
+        if let Some(start) = self
+            .including
+            .iter()
+            .position(|(file, _)| *file == canonical)
+        {
+            let mut path: Vec<_> = self.including[start..]
+                .iter()
+                .map(|(_, display)| display.clone())
+                .collect();
+            path.push(display);
+            return Err(CircularInclude { path, span });
+        }
+
+        // Each file is only included once
+        if !self.included.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let text = fs::read_to_string(&canonical).map_err(fail)?;
+
+        let mut including = self.including.clone();
+        including.push((canonical.clone(), display.clone()));
+        let mut builder = Builder {
+            dir: canonical
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            including,
+            included: mem::take(&mut self.included),
+            is_included: true,
+            ..Builder::new()
+        };
+
+        // Included file must be valid on its own
+        let mut errors = builder.statements(&text);
+        builder.resolve(&mut errors);
+        self.included = mem::take(&mut builder.included);
+
+        if !errors.is_empty() {
+            self.include_errors
+                .extend(errors.into_iter().map(|err| InFile {
+                    path: display.clone(),
+                    text: text.clone(),
+                    span,
+                    source: Box::new(err),
+                }));
+            return Ok(());
+        }
+
+        self.merge(builder, &display);
+        Ok(())
+    }
+
+    /// Add definitions of included file
+    ///
+    /// Classes and weights defined by this file are kept. Positions of definitions are not kept,
+    /// as they are in another file, but rules keep the path of the file that defined them
+    fn merge(&mut self, included: Builder, path: &str) {
+        for (name, value) in included.raw_classes {
+            if self.raw_classes.contains_key(&name) && !self.included_classes.contains(&name) {
+                continue;
+            }
+            self.raw_classes.insert(name.clone(), value);
+            self.class_positions.remove(&name);
+            self.included_classes.insert(name);
         }
-        let classes = classes;
+
+        for (segment, weight) in included.weights {
+            if self.weights.contains_key(&segment) && !self.included_weights.contains(&segment) {
+                continue;
+            }
+            self.weights.insert(segment.clone(), weight);
+            self.included_weights.insert(segment);
+        }
+
+        self.syllables.extend(
+            included
+                .syllables
+                .into_iter()
+                .map(|(syllable, _)| (syllable, Span::default())),
+        );
+
+        // Reasons of included rules are added after reasons of this file
+        let offset = self.reasons.len();
+        self.rules
+            .extend(included.rules.into_iter().map(|rule| RawRule {
+                reason_ref: rule.reason_ref.map(|reason| reason + offset),
+                // Rule may be from a file included by included file
+                file: rule.file.or_else(|| Some(path.to_string())),
+                positions: Vec::new(),
+                ..rule
+            }));
+        self.reasons.extend(included.reasons);
+    }
+
+    /// Resolve classes, check syllable templates, and convert rules to regex
+    ///
+    /// Syllable templates and rules with errors are left out. Errors are added to `errors`
+    fn resolve(&self, errors: &mut Vec<Error>) -> (Classes, Vec<Syllable>, Vec<Rule>) {
+        // Substitute classes in class values
+        let classes = resolve_classes(&self.raw_classes, &self.class_positions, errors);
+
+        // Check that classes in syllable templates exist
+        let syllables = self
+            .syllables
+            .iter()
+            .filter(|(syllable, span)| {
+                let mut names = Vec::new();
+                for part in &syllable.parts {
+                    part.class_names(&mut names);
+                }
+
+                let mut valid = true;
+                for name in names {
+                    if !classes.contains_key(name) {
+                        // Class with error was already reported
+                        if !self.raw_classes.contains_key(name) {
+                            errors.push(ClassNotFound {
+                                name: name.to_string(),
+                                span: *span,
+                                suggestion: similar_name(name, &self.raw_classes),
+                            });
+                        }
+                        valid = false;
+                    }
+                }
+                valid
+            })
+            .map(|(syllable, _)| syllable.clone())
+            .collect();
 
         // Convert rules to regex rules
-        let rules = make_regex(rules, &classes)?;
+        let rules = make_regex(&self.rules, &classes, &self.raw_classes, errors);
+
+        (classes, syllables, rules)
+    }
 
-        // Use default mode if not given
-        let mode = mode.unwrap_or_default();
+    /// Build scheme from definitions
+    ///
+    /// Errors are added to `errors`
+    fn build(self, errors: &mut Vec<Error>) -> Phonet {
+        let (classes, syllables, rules) = self.resolve(errors);
 
-        Ok(Phonet {
+        Phonet {
+            matcher: Matcher::new(&rules),
             rules,
-            tests,
-            reasons,
+            tests: self.tests,
+            reasons: self.reasons,
             classes,
-            mode,
-            mini,
-        })
+            weights: self.weights,
+            syllables,
+            // Use default mode if not given
+            mode: self.mode.unwrap_or_default(),
+            mini: self.mini,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Mode {
+    Romanized,
+    Broad,
+    Narrow,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Self::Romanized
+    }
+}
+
+/// Scheme parsed from file
+///
+/// Holds rules and tests
+#[derive(Debug)]
+pub struct Phonet {
+    /// Defined rules
+    ///
+    /// If rules are changed, use `Phonet::compile_rules` to keep validation fast
+    pub rules: Vec<Rule>,
+    /// Tests to run
+    pub tests: Vec<TestDefinition>,
+    /// Defined reasons values for rules
+    pub reasons: Vec<String>,
+    /// Classes
+    pub classes: Classes,
+    /// Relative weights of segments, for generating words
+    ///
+    /// Segments without a weight have a weight of `1.0`
+    pub weights: Weights,
+    /// Syllable templates, for generating words by syllable
+    pub syllables: Vec<Syllable>,
+    /// Mode - This is only semantical
+    pub mode: Mode,
+    /// Minified statements, in order of definition
+    mini: Vec<Mini>,
+    /// Rules combined for faster validation
+    matcher: Matcher,
+}
+
+impl Phonet {
+    /// Get count of tests in list
+    pub fn test_count(&self) -> usize {
+        self.tests
+            .iter()
+            .filter(|item| matches!(item, TestDefinition::Test { .. }))
+            .count()
+    }
+
+    /// Parse `Phonet` from string
+    ///
+    /// Returns the first error in file. Use `Phonet::parse_recover` to get every error
+    pub fn parse(file: &str) -> Result<Phonet, Error> {
+        let (scheme, errors) = Self::parse_recover(file);
+
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(scheme),
+        }
+    }
+
+    /// Parse `Phonet` from string, continuing after errors
+    ///
+    /// Returns every error in file, in order found, with scheme built from the statements without
+    /// errors. Rules and syllable templates which use a class with an error are left out, and that
+    /// error is only reported once
+    pub fn parse_recover(file: &str) -> (Phonet, Vec<Error>) {
+        Builder::new().parse(file)
+    }
+
+    /// Parse `Phonet` from file
+    ///
+    /// Included files are relative to directory of file. Returns the first error in file
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<Phonet, Error> {
+        let path = path.as_ref();
+        let file = fs::read_to_string(path).map_err(|err| ReadFail {
+            path: path.display().to_string(),
+            reason: err.to_string(),
+        })?;
+
+        let (scheme, errors) = Self::parse_recover_at(&file, path);
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(scheme),
+        }
+    }
+
+    /// Parse `Phonet` from string, which was read from file at `path`, continuing after errors
+    ///
+    /// Included files are relative to directory of `path`. See `Phonet::parse_recover`
+    pub fn parse_recover_at(file: &str, path: impl AsRef<Path>) -> (Phonet, Vec<Error>) {
+        let path = path.as_ref();
+        let mut builder = Builder {
+            dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            ..Builder::new()
+        };
+
+        // File cannot include itself
+        if let Ok(canonical) = path.canonicalize() {
+            builder.included.insert(canonical.clone());
+            builder
+                .including
+                .push((canonical, path.display().to_string()));
+        }
+
+        builder.parse(file)
     }
 
     /// Minify Phonet scheme as string
+    ///
+    /// Statements are kept in order, so the minified file defines the same scheme, with the same
+    /// reasons for rules. Without tests, tests and notes are left out
     pub fn minify(&self, do_tests: bool) -> String {
-        let s = ';';
-        let c = self.mini.classes.join(";");
-        let r = self.mini.rules.join(";");
-
-        if do_tests {
-            // Include tests
-            format!(
-                "{c}{s}{r}{s}?+{tp}{s}?!{tn}",
-                tp = self.mini.tests_pos.join(" "),
-                tn = self.mini.tests_neg.join(" "),
-            )
-        } else {
-            // Don't include tests
-            format!("{c}{s}{r}")
-        }
+        self.mini
+            .iter()
+            .filter_map(|statement| match statement {
+                Mini::Definition(text) => Some(text.clone()),
+                Mini::Test(text) => do_tests.then(|| text.clone()),
+                Mini::Reason { reason, noted } => Some(if *noted && do_tests {
+                    format!("@*{reason}")
+                } else {
+                    format!("@{reason}")
+                }),
+            })
+            .map(|text| escape_multiline(&text))
+            .collect::<Vec<_>>()
+            .join(";")
     }
 
     /// Run tests, return results
     pub fn run(&self) -> Results {
         Results::run(self)
     }
+
+    /// Run tests with options, return results
+    pub fn run_with(&self, options: &RunOptions) -> Results {
+        Results::run_with(self, options)
+    }
+
+    /// Get every rule that word does not follow, in order of definition
+    ///
+    /// Word is valid if list is empty
+    pub fn violations(&self, word: &str) -> Vec<Violation> {
+        self.all_failing_rules(word)
+            .into_iter()
+            .map(|index| self.violation(word, index))
+            .collect()
+    }
+
+    /// Get first rule that word does not follow, or `None` if word is valid
+    ///
+    /// This is the rule that a test of the word fails with
+    pub fn first_violation(&self, word: &str) -> Option<Violation> {
+        self.first_failing_rule(word)
+            .map(|index| self.violation(word, index))
+    }
+
+    /// Combine rules again, after rules were changed
+    ///
+    /// Rules which need no fancy regex features, such as look-around or backreferences, are matched
+    /// together in a single pass. This is done when parsing, so is only needed if `rules` is changed
+    pub fn compile_rules(&mut self) {
+        self.matcher = Matcher::new(&self.rules);
+    }
+
+    /// Get amount of rules which are matched together, as they need no fancy regex features
+    pub fn combined_rule_count(&self) -> usize {
+        self.matcher.combined_count()
+    }
+
+    /// Get segments which have a weight, but are not in any class, in order of segment
+    ///
+    /// Weights of these segments are never used when generating words. Classes which cannot be
+    /// expanded to a list of segments are checked by matching the whole segment with the class
+    pub fn unknown_weights(&self) -> Vec<&str> {
+        let mut unknown: Vec<&str> = self
+            .weights
+            .keys()
+            .map(String::as_str)
+            .filter(|segment| {
+                !self
+                    .classes
+                    .iter()
+                    .any(|(name, value)| self.class_contains(name, value, segment))
+            })
+            .collect();
+        unknown.sort_unstable();
+        unknown
+    }
+
+    /// Returns `true` if class has segment
+    fn class_contains(&self, name: &str, value: &str, segment: &str) -> bool {
+        match self.inventory(name) {
+            Ok(segments) => segments.iter().any(|item| item == segment),
+            Err(_) => Regex::new(&format!("^(?:{value})$"))
+                .is_ok_and(|regex| regex.is_match(segment).unwrap_or(false)),
+        }
+    }
+
+    /// Get index of first rule that word does not follow
+    pub(crate) fn first_failing_rule(&self, word: &str) -> Option<usize> {
+        self.matcher.first_failing(word, &self.rules)
+    }
+
+    /// Get indexes of every rule that word does not follow, in order
+    pub(crate) fn all_failing_rules(&self, word: &str) -> Vec<usize> {
+        self.matcher.all_failing(word, &self.rules)
+    }
+
+    /// Get violation of rule at index, by word
+    fn violation(&self, word: &str, index: usize) -> Violation {
+        let rule = &self.rules[index];
+        Violation {
+            intent: rule.intent,
+            line: rule.line,
+            file: rule.file.clone(),
+            reason: rule
+                .reason_ref
+                .and_then(|reason| self.reasons.get(reason))
+                .cloned(),
+            span: match_span(word, rule),
+        }
+    }
 }
 
 /// Substitute classes in rule and create regex
-fn make_regex(raw_rules: Vec<RawRule>, classes: &Classes) -> Result<Vec<Rule>, Error> {
+///
+/// Rules with errors are left out, and errors are added to `errors`
+fn make_regex(
+    raw_rules: &[RawRule],
+    classes: &Classes,
+    raw_classes: &Classes,
+    errors: &mut Vec<Error>,
+) -> Vec<Rule> {
     let mut rules: Vec<Rule> = Vec::new();
 
     for RawRule {
@@ -344,38 +861,66 @@ fn make_regex(raw_rules: Vec<RawRule>, classes: &Classes) -> Result<Vec<Rule>, E
         pattern,
         reason_ref,
         line,
+        file,
+        positions,
     } in raw_rules
     {
-        let pat = substitute_classes(&pattern, classes, line)?;
+        let pat = match substitute_classes(pattern, classes, positions) {
+            Ok(x) => x,
+            // Class with error was already reported
+            Err(ClassNotFound { name, .. }) if raw_classes.contains_key(&name) => continue,
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
         let pattern = match Regex::new(&pat) {
             Ok(x) => x,
-            Err(err) => return Err(RegexFail { err, line }),
+            Err(err) => {
+                errors.push(RegexFail {
+                    err,
+                    span: Span::covering(positions),
+                });
+                continue;
+            }
         };
 
         rules.push(Rule {
-            intent,
+            intent: *intent,
             pattern,
-            reason_ref,
+            reason_ref: *reason_ref,
+            line: *line,
+            file: file.clone(),
         });
     }
 
-    Ok(rules)
+    rules
 }
 
 /// Substitute class names regex rule with class values (recursively)
 ///
 /// `pattern` argument must not contain spaces
-fn substitute_classes(pattern: &str, classes: &Classes, line: usize) -> Result<String, Error> {
+///
+/// `positions` holds the position of each character of pattern, for errors. If positions are not
+/// known for each character, the span of all positions is used instead
+fn substitute_classes(
+    pattern: &str,
+    classes: &Classes,
+    positions: &[Span],
+) -> Result<String, Error> {
     let mut output = String::new();
 
-    // Build class name
-    let mut name_build: Option<String> = None;
+    // Build class name, with index of opening bracket
+    let mut name_build: Option<(String, usize)> = None;
 
     // Replace `<` and `>` with `⟨` and `⟩` respectively, where classes are
     let pattern = replace_angle_brackets(pattern);
 
+    // Get span of characters in pattern
+    let locate = |range| Span::locate(positions, range);
+
     // Loop characters
-    for ch in pattern.chars() {
+    for (i, ch) in pattern.chars().enumerate() {
         match ch {
             // Open class name
             // Check that not in lookbehind
@@ -384,46 +929,49 @@ fn substitute_classes(pattern: &str, classes: &Classes, line: usize) -> Result<S
                     // Name is already building - Another opening bracket should not be there
                     return Err(Error::ClassUnexpectedOpenName {
                         pattern: pattern.to_string(),
-                        line,
+                        span: locate(i..i + 1),
                     });
                 }
 
                 // Start building name
-                name_build = Some(String::new());
+                name_build = Some((String::new(), i));
             }
 
             // Close class name
             '⟩' => {
                 // Get class name
-                let name = match name_build {
+                let (name, start) = match name_build {
                     Some(x) => x,
                     None => {
                         // No name is building - Closing bracket should not be there
                         return Err(Error::ClassUnexpectedCloseName {
                             pattern: pattern.to_string(),
-                            line,
+                            span: locate(i..i + 1),
                         });
                     }
                 };
+                let span = locate(start..i + 1);
 
                 // Get class value
                 let Some(value) = classes.get(&name) else {
-            // Class name was not found
-            return Err(Error::ClassNotFound {
-                name,
-                line,
-            });
-        };
+                    // Class name was not found
+                    return Err(Error::ClassNotFound {
+                        suggestion: similar_name(&name, classes),
+                        name,
+                        span,
+                    });
+                };
 
                 // Add value to output (recursively)
-                output.push_str(&substitute_classes(value, classes, line)?);
+                // Errors in value are shown at class name
+                output.push_str(&substitute_classes(value, classes, &[span])?);
                 // Finish building name
                 name_build = None;
             }
 
             // Normal character
             _ => {
-                if let Some(name) = &mut name_build {
+                if let Some((name, _)) = &mut name_build {
                     // Name is building - push to name
                     name.push(ch);
                 } else {
@@ -435,13 +983,68 @@ fn substitute_classes(pattern: &str, classes: &Classes, line: usize) -> Result<S
     }
 
     // Class name was not finished building, before end of end of pattern
-    if name_build.is_some() {
-        return Err(Error::ClassUnexpectedEnd { pattern, line });
+    if let Some((_, start)) = name_build {
+        let span = locate(start..pattern.chars().count());
+        return Err(Error::ClassUnexpectedEnd { pattern, span });
     }
 
     Ok(output)
 }
 
+/// Get position of each character of part of statement, except spaces
+fn positions_without_spaces(statement: &Statement, part: &str) -> Vec<Span> {
+    part.chars()
+        .zip(statement.char_spans(part))
+        .filter(|(ch, _)| *ch != ' ')
+        .map(|(_, span)| *span)
+        .collect()
+}
+
+/// Get name of class with similar name, if any
+///
+/// Names are similar if they differ by case, or by a few characters
+fn similar_name(name: &str, classes: &Classes) -> Option<String> {
+    // Allow 1 edit for every 3 characters, at least 1
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut names: Vec<&String> = classes.keys().collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|other| {
+            let distance = if other.to_lowercase() == name.to_lowercase() {
+                0
+            } else {
+                edit_distance(name, other)
+            };
+            (distance, other)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, other)| other.to_string())
+}
+
+/// Get amount of single character insertions, deletions, or substitutions to change one string into
+/// another (Levenshtein distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    // Distances for previous character of `a`, to each prefix of `b`
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ch_a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, ch_b) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ch_a != *ch_b);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
 /// Replace ascii `<` and `>` with `⟨` and `⟩` respectively, for classes
 ///
 /// Does not replace `<` and `>` with use in look-behinds or named group definitions or references
@@ -482,41 +1085,57 @@ mod tests {
         ]);
 
         assert_eq!(
-            substitute_classes("<C>", &classes, 0).unwrap(),
+            substitute_classes("<C>", &classes, &[]).unwrap(),
             "[ptk]".to_string()
         );
 
         assert_eq!(
-            substitute_classes("<C>-<Vowels>", &classes, 0).unwrap(),
+            substitute_classes("<C>-<Vowels>", &classes, &[]).unwrap(),
             "[ptk]-[aio]".to_string()
         );
 
         assert_eq!(
-            substitute_classes("<_>", &classes, 0).unwrap(),
+            substitute_classes("<_>", &classes, &[]).unwrap(),
             "[[ptk][aio]]".to_string()
         );
 
         assert_eq!(
-            substitute_classes("(?<=1)", &classes, 0).unwrap(),
+            substitute_classes("(?<=1)", &classes, &[]).unwrap(),
             "(?<=1)".to_string()
         );
 
         assert_eq!(
-            substitute_classes("(?<abc><C>)", &classes, 0).unwrap(),
+            substitute_classes("(?<abc><C>)", &classes, &[]).unwrap(),
             "(?<abc>[ptk])".to_string()
         );
 
-        assert_eq!(substitute_classes("a>b", &classes, 0).unwrap(), "a>b");
-        assert_eq!(substitute_classes("a<b", &classes, 0).unwrap(), "a<b");
+        assert_eq!(substitute_classes("a>b", &classes, &[]).unwrap(), "a>b");
+        assert_eq!(substitute_classes("a<b", &classes, &[]).unwrap(), "a<b");
 
-        assert!(match substitute_classes("<c>", &classes, 0) {
+        assert!(match substitute_classes("<c>", &classes, &[]) {
             Err(Error::ClassNotFound { .. }) => true,
             _ => false,
         });
 
-        assert!(match substitute_classes("<a<b>c>", &classes, 0) {
+        assert!(match substitute_classes("<a<b>c>", &classes, &[]) {
             Err(Error::ClassNotFound { .. }) => true,
             _ => false,
         });
     }
+    #[test]
+    fn similar_name_works() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+
+        let classes = Classes::from([
+            ("C".to_string(), "[ptk]".to_string()),
+            ("Vowel".to_string(), "[aio]".to_string()),
+        ]);
+
+        assert_eq!(similar_name("c", &classes).as_deref(), Some("C"));
+        assert_eq!(similar_name("Vowl", &classes).as_deref(), Some("Vowel"));
+        assert_eq!(similar_name("VOWEL", &classes).as_deref(), Some("Vowel"));
+        assert_eq!(similar_name("Nasal", &classes), None);
+    }
 }
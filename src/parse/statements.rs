@@ -1,101 +1,83 @@
-/// Split file into list of statements
-pub fn split_statements(file: &str) -> Vec<(String, usize)> {
-    // Vector of statements
-    // Multiline statements are treated as single line, with linebreaks removed
-    let mut statements = vec![];
-
-    // Building single line (and multiline, before '&' character)
-    let mut build_line = String::new();
-    // Building multiline, optional
-    let mut build_multiline: Option<(String, usize)> = None;
-
-    // Canon line number of statement
-    // Multiline uses line number of beginning of statement
-    let mut current_line_number = 1;
-
-    // Loop characters of file
-    for ch in file.chars() {
-        match ch {
-            // Newline or semicolon without multiline
-            '\n' | ';' if build_multiline.is_none() => {
-                // If single line is not empty
-                if !build_line.is_empty() {
-                    // Push single line to statement
-                    statements.push((build_line, current_line_number));
-                    // Reset single line
-                    build_line = String::new();
-                }
-            }
-
-            // Newline with multiline - Ignore
-            '\n' => (),
-
-            // Semicolon with multiline
-            ';' => {
-                // Multiline is active
-                // Unwrap should not fail due to above match guard
-                let (multiline, number) = build_multiline.unwrap();
-
-                // Add multiline to single line, without linebreaks
-                build_line.push_str(&multiline);
-                // Reset multiline
-                build_multiline = None;
-
-                // If single line (including multiline) is not empty
-                // This mirrors the statement in arm of '\n' match, above
-                if !build_line.is_empty() {
-                    // Push single line to statement
-                    statements.push((build_line, number));
-                    // Reset single line
-                    build_line = String::new();
-                }
-            }
+use std::ops::Range;
+
+use crate::{syntax::SyntaxTree, types::Span};
+
+/// Statement of file, with position of each character in file
+#[derive(Debug, PartialEq)]
+pub struct Statement {
+    /// Text of statement
+    ///
+    /// Multiline statements are treated as single line, with linebreaks removed
+    pub text: String,
+    /// Line number of statement
+    ///
+    /// Multiline uses line number of beginning of statement
+    pub line: usize,
+    /// Position of each character of text, in file
+    positions: Vec<Span>,
+}
 
-            // Start multiline
-            '&' => match &mut build_multiline {
-                // Multiline is not already active
-                None => {
-                    // Start multiline, with current line number
-                    build_multiline = Some((String::new(), current_line_number))
-                }
-
-                // Multiline is already active
-                Some(_) => {
-                    // Add '&' character to single line build
-                    build_line.push(ch);
-                }
-            },
-
-            // Add other character to single line build
-            _ => build_line.push(ch),
+impl Statement {
+    /// Get span of part of statement
+    ///
+    /// `part` must be a slice of `text`
+    pub fn span(&self, part: &str) -> Span {
+        let range = self.range(part);
+
+        // Empty part - Use position of next character, with no length
+        if range.is_empty() {
+            return match self.positions.get(range.start) {
+                Some(span) => Span { len: 0, ..*span },
+                None => match self.positions.last() {
+                    Some(span) => Span {
+                        column: span.column + span.len,
+                        len: 0,
+                        ..*span
+                    },
+                    None => Span::default(),
+                },
+            };
         }
 
-        // Increase canon line number
-        if ch == '\n' {
-            current_line_number += 1;
-        }
+        Span::covering(&self.positions[range])
     }
 
-    // Get line number of statement
-    let start_line_number = match build_multiline {
-        // Multiline is not active - Use current line number (last line)
-        None => current_line_number,
-        // Multiline is active
-        Some((multiline, number)) => {
-            // Add multiline to single line, without linebreaks
-            build_line.push_str(&multiline);
-            // Use line number of beginning of statement
-            number
-        }
-    };
+    /// Get position of each character of part of statement
+    ///
+    /// `part` must be a slice of `text`
+    pub fn char_spans(&self, part: &str) -> &[Span] {
+        &self.positions[self.range(part)]
+    }
 
-    // If single line (including multiline) is not empty
-    if !build_line.is_empty() {
-        // Push single line to statement
-        statements.push((build_line, start_line_number));
+    /// Get range of characters of part of statement
+    fn range(&self, part: &str) -> Range<usize> {
+        let offset = (part.as_ptr() as usize)
+            .checked_sub(self.text.as_ptr() as usize)
+            .filter(|offset| offset + part.len() <= self.text.len())
+            .expect("Part is not a slice of statement. This error should NEVER APPEAR!");
+
+        let start = self.text[..offset].chars().count();
+        start..start + part.chars().count()
     }
+}
 
-    statements
+/// Split file into list of statements
+///
+/// Blank statements are skipped
+pub fn split_statements(file: &str) -> Vec<Statement> {
+    SyntaxTree::parse(file)
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let (text, positions): (String, Vec<Span>) = node.chars().unzip();
+
+            (!text.is_empty()).then_some(Statement {
+                text,
+                line: node.span.line,
+                positions,
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -104,7 +86,7 @@ mod tests {
 
     #[test]
     fn split_statements_works() {
-        let lhs = split_statements(
+        let statements = split_statements(
             &[
                 "foo bar & abc 123;",
                 "baz &",
@@ -121,6 +103,10 @@ mod tests {
             ]
             .join("\n"),
         );
+        let lhs: Vec<_> = statements
+            .iter()
+            .map(|statement| (statement.text.clone(), statement.line))
+            .collect();
 
         let rhs = vec![
             ("foo bar  abc 123".to_string(), 1),
@@ -152,5 +138,45 @@ mod tests {
         println!("\x1b[36;1m---\x1b[0m");
 
         assert_eq!(lhs, rhs);
+
+        // Positions of characters in multiline statement
+        let statement = &statements[1];
+        let span = |part: &str| {
+            let start = statement.text.find(part).unwrap();
+            statement.span(&statement.text[start..start + part.len()])
+        };
+        assert_eq!(
+            span("baz"),
+            Span {
+                line: 2,
+                column: 1,
+                len: 3
+            }
+        );
+        assert_eq!(
+            span("456"),
+            Span {
+                line: 3,
+                column: 5,
+                len: 3
+            }
+        );
+        assert_eq!(
+            span("abc"),
+            Span {
+                line: 4,
+                column: 2,
+                len: 3
+            }
+        );
+        // Only first line of part spanning multiple lines
+        assert_eq!(
+            span("456 abc"),
+            Span {
+                line: 3,
+                column: 5,
+                len: 3
+            }
+        );
     }
 }
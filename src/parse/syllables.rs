@@ -0,0 +1,144 @@
+use std::{
+    iter::{Enumerate, Peekable},
+    str::Chars,
+};
+
+use super::replace_angle_brackets;
+use crate::types::{Error, Span, SyllablePart};
+
+/// Characters of pattern, with index
+type Input<'a> = Peekable<Enumerate<Chars<'a>>>;
+
+/// Parse syllable template into list of parts
+///
+/// `pattern` argument must not contain spaces
+///
+/// `positions` holds the position of each character of pattern, for errors
+pub fn parse_syllable(pattern: &str, positions: &[Span]) -> Result<Vec<SyllablePart>, Error> {
+    // Replace `<` and `>` with `⟨` and `⟩` respectively, where classes are
+    let pattern = replace_angle_brackets(pattern);
+    let mut chars = pattern.chars().enumerate().peekable();
+
+    let parts = parse_parts(&mut chars, positions)?;
+
+    // Parts only stop early at an unmatched closing bracket
+    if let Some((i, _)) = chars.next() {
+        return Err(Error::InvalidSyllable {
+            reason: "Unexpected closing bracket (`)`)".to_string(),
+            span: Span::locate(positions, i..i + 1),
+        });
+    }
+
+    if parts.is_empty() {
+        return Err(Error::InvalidSyllable {
+            reason: "Syllable template is empty".to_string(),
+            span: Span::covering(positions),
+        });
+    }
+
+    Ok(parts)
+}
+
+/// Parse parts until end of pattern or closing round bracket
+fn parse_parts(chars: &mut Input, positions: &[Span]) -> Result<Vec<SyllablePart>, Error> {
+    let mut parts = Vec::new();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch == ')' {
+            break;
+        }
+        chars.next();
+
+        let part = match ch {
+            // Class name
+            '⟨' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '⟩')) => break,
+                        Some((_, ch)) => name.push(ch),
+                        None => {
+                            return Err(Error::InvalidSyllable {
+                                reason: "Class name was not closed with bracket (`>`)".to_string(),
+                                span: Span::locate(positions, start..start + 1),
+                            })
+                        }
+                    }
+                }
+                SyllablePart::Class(name)
+            }
+
+            // Group
+            '(' => {
+                let group = parse_parts(chars, positions)?;
+                if chars.next().map(|(_, ch)| ch) != Some(')') {
+                    return Err(Error::InvalidSyllable {
+                        reason: "Group was not closed with bracket (`)`)".to_string(),
+                        span: Span::locate(positions, start..start + 1),
+                    });
+                }
+                SyllablePart::Group(group)
+            }
+
+            '?' | '⟩' | '<' | '>' => {
+                return Err(Error::InvalidSyllable {
+                    reason: format!("Unexpected character `{ch}`"),
+                    span: Span::locate(positions, start..start + 1),
+                })
+            }
+
+            // Literal character
+            _ => SyllablePart::Literal(ch.to_string()),
+        };
+
+        // Optional part
+        if chars.peek().map(|(_, ch)| *ch) == Some('?') {
+            chars.next();
+            parts.push(SyllablePart::Optional(Box::new(part)));
+        } else {
+            parts.push(part);
+        }
+    }
+
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SyllablePart::*;
+
+    fn class(name: &str) -> SyllablePart {
+        Class(name.to_string())
+    }
+
+    fn optional(part: SyllablePart) -> SyllablePart {
+        Optional(Box::new(part))
+    }
+
+    #[test]
+    fn parse_syllable_works() {
+        assert_eq!(
+            parse_syllable("<S>?<C>?l?<V><A>?<K>?", &[]).unwrap(),
+            vec![
+                optional(class("S")),
+                optional(class("C")),
+                optional(Literal("l".to_string())),
+                class("V"),
+                optional(class("A")),
+                optional(class("K")),
+            ]
+        );
+
+        assert_eq!(
+            parse_syllable("(<C>⟨l⟩)?<V>", &[]).unwrap(),
+            vec![optional(Group(vec![class("C"), class("l")])), class("V")]
+        );
+
+        assert!(parse_syllable("", &[]).is_err());
+        assert!(parse_syllable("?<V>", &[]).is_err());
+        assert!(parse_syllable("<V", &[]).is_err());
+        assert!(parse_syllable("(<V>", &[]).is_err());
+        assert!(parse_syllable("<V>)", &[]).is_err());
+    }
+}
@@ -1,4 +1,9 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
 use crate::{
+    coverage::Coverage,
     types::{Rule, TestDefinition, TestResult},
     DisplayLevel::{self, *},
     Phonet,
@@ -6,24 +11,81 @@ use crate::{
 use FailReason::*;
 use ValidStatus::*;
 
+/// Options for running tests
+///
+/// Use with `Results::run_with`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunOptions {
+    /// Find every rule that each test does not follow, not just the first
+    ///
+    /// Tests are added to results as `TestResult::Detailed`, instead of `TestResult::Test`
+    pub all_violations: bool,
+    /// Record how many tests each rule rejected
+    ///
+    /// Coverage is added to `Results::coverage`
+    pub coverage: bool,
+}
+
+/// Rule which a word does not follow
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// Intent of rule
+    pub intent: bool,
+    /// Line number of rule definition
+    pub line: usize,
+    /// Path of file which rule was defined in, if rule was included from another file
+    pub file: Option<String>,
+    /// Reason of rule, if given
+    pub reason: Option<String>,
+    /// Byte range of word which matched rule, if rule is negative
+    pub span: Option<Range<usize>>,
+}
+
+impl Violation {
+    /// Get line of rule definition, with file if rule was included from another file
+    pub fn location(&self) -> String {
+        location(self.line, self.file.as_deref())
+    }
+}
+
+/// Format line number, with path of file if not the file being run
+pub(crate) fn location(line: usize, file: Option<&str>) -> String {
+    match file {
+        Some(file) => format!("line {line} of `{file}`"),
+        None => format!("line {line}"),
+    }
+}
+
 /// Results from run tests
 ///
 /// Create with `PhonetResults::run()`
+#[derive(Serialize)]
 pub struct Results {
     /// List of results of each test
     pub list: Vec<TestResult>,
     /// Amount of failed tests
     pub fail_count: u32,
+    /// Amount of tests each rule rejected, if run with `RunOptions::coverage`
+    pub coverage: Option<Coverage>,
 }
 
 impl Results {
     /// Run tests, return results
     pub fn run(scheme: &Phonet) -> Results {
+        Self::run_with(scheme, &RunOptions::default())
+    }
+
+    /// Run tests with options, return results
+    pub fn run_with(scheme: &Phonet, options: &RunOptions) -> Results {
+        // Coverage of rules, if required
+        let mut coverage = options.coverage.then(|| Coverage::new(scheme));
+
         // No tests
         if scheme.tests.is_empty() {
             return Results {
                 list: Vec::new(),
                 fail_count: 0,
+                coverage,
             };
         }
 
@@ -34,18 +96,50 @@ impl Results {
 
         // Loop tests
         for test in &scheme.tests {
+            // Record rules which test does not follow
+            if let (Some(coverage), TestDefinition::Test { intent, word }) = (&mut coverage, test) {
+                coverage.record(scheme, word, *intent);
+            }
+
             match test {
                 // Note - simply add to list
                 TestDefinition::Note(note) => list.push(TestResult::Note(note.to_string())),
 
+                // Test - Find every rule that test does not follow
+                TestDefinition::Test { intent, word } if options.all_violations => {
+                    let violations = scheme.violations(word);
+
+                    // Check if validity status with test intent
+                    let pass = !(violations.is_empty() ^ intent);
+
+                    // Increase fail count if failed
+                    if !pass {
+                        fail_count += 1;
+                    }
+
+                    // Add test result to list
+                    list.push(TestResult::Detailed {
+                        intent: *intent,
+                        word: word.to_string(),
+                        pass,
+                        violations,
+                    });
+                }
+
                 // Test - Validate test, check validity with intent, create reason for failure
                 TestDefinition::Test { intent, word } => {
                     // Validate test
-                    let validity = validate_test(word, &scheme.rules);
+                    let failing = scheme.first_failing_rule(word);
+                    let validity = status_of(failing, scheme);
 
                     // Check if validity status with test intent
                     let pass = !(validity.is_valid() ^ intent);
 
+                    // Part of word which matched negative rule
+                    let span = failing
+                        .filter(|_| !pass)
+                        .and_then(|index| match_span(word, &scheme.rules[index]));
+
                     // Create reason
                     let reason = if !pass {
                         // Test failed - Some reason
@@ -71,12 +165,17 @@ impl Results {
                         word: word.to_string(),
                         pass,
                         reason,
+                        span,
                     });
                 }
             }
         }
 
-        Results { list, fail_count }
+        Results {
+            list,
+            fail_count,
+            coverage,
+        }
     }
 
     /// Get maximum length of all test words
@@ -85,7 +184,8 @@ impl Results {
             .iter()
             .map(|x| match x {
                 // Test - Check display level
-                TestResult::Test { word, pass, .. } => match display_level {
+                TestResult::Test { word, pass, .. }
+                | TestResult::Detailed { word, pass, .. } => match display_level {
                     // Always include
                     ShowAll => word.len(),
                     // Only include if failed
@@ -105,7 +205,7 @@ impl Results {
     pub fn test_count(&self) -> usize {
         self.list
             .iter()
-            .filter(|item| matches!(item, TestResult::Test { .. }))
+            .filter(|item| matches!(item, TestResult::Test { .. } | TestResult::Detailed { .. }))
             .count()
     }
 
@@ -150,16 +250,10 @@ impl Results {
                     word,
                     pass,
                     reason,
+                    span,
                 } => {
                     // Skip if not required by display level
-                    if match display_level {
-                        // Always show
-                        ShowAll => false,
-                        // Only show if failed
-                        NotesAndFails | JustFails if !pass => false,
-                        // Else skip
-                        _ => true,
-                    } {
+                    if !should_display(display_level, *pass) {
                         continue;
                     }
 
@@ -178,20 +272,62 @@ impl Results {
                     };
 
                     // Display test status
-                    if no_color {
-                        println!(
-                            " {intent} {word}{space}  {result} {reason}",
-                            intent = if *intent { "✔" } else { "✗" },
-                            space = " ".repeat(max_word_len - word.chars().count()),
-                            result = if *pass { "pass" } else { "FAIL" },
-                        );
-                    } else {
-                        println!(
-              "  \x1b[{intent}\x1b[0m {word}{space}  \x1b[1;{result} \x1b[0;3;1m{reason}\x1b[0m",
-              intent = if *intent { "36m✔" } else { "35m✗" },
-              space = " ".repeat(max_word_len - word.chars().count()),
-              result = if *pass { "32mpass" } else { "31mFAIL" },
-            );
+                    display_test(
+                        (*intent, word, *pass),
+                        reason,
+                        span.as_ref(),
+                        max_word_len,
+                        no_color,
+                    );
+                }
+
+                // Display test, with every rule it does not follow
+                TestResult::Detailed {
+                    intent,
+                    word,
+                    pass,
+                    violations,
+                } => {
+                    // Skip if not required by display level
+                    if !should_display(display_level, *pass) {
+                        continue;
+                    }
+
+                    // Passed, or valid but should be invalid
+                    if *pass || violations.is_empty() {
+                        let reason = match (*pass, no_color) {
+                            (true, _) => "",
+                            (false, true) => "Valid, but should be invalid",
+                            (false, false) => "\x1b[33mValid, but should be invalid\x1b[0m",
+                        };
+                        display_test((*intent, word, *pass), reason, None, max_word_len, no_color);
+                        continue;
+                    }
+
+                    // Display first violation with test status, then others below it
+                    // Highlight part of word which matched first violation
+                    let span = violations.first().and_then(|violation| violation.span.as_ref());
+
+                    let mut violations = violations.iter().map(|violation| {
+                        format!(
+                            "{reason} ({location})",
+                            reason = violation.reason.as_deref().unwrap_or("No reason given"),
+                            location = violation.location(),
+                        )
+                    });
+
+                    if let Some(first) = violations.next() {
+                        display_test((*intent, word, *pass), &first, span, max_word_len, no_color);
+                    }
+
+                    // Align with reason of first line
+                    let indent = " ".repeat(max_word_len + if no_color { 10 } else { 11 });
+                    for violation in violations {
+                        if no_color {
+                            println!("{indent}{violation}");
+                        } else {
+                            println!("{indent}\x1b[3;1m{violation}\x1b[0m");
+                        }
                     }
                 }
             }
@@ -224,7 +360,68 @@ impl Results {
     }
 }
 
+/// Returns `true` if test should be displayed with display level
+fn should_display(display_level: DisplayLevel, pass: bool) -> bool {
+    match display_level {
+        // Always show
+        ShowAll => true,
+        // Only show if failed
+        NotesAndFails | JustFails => !pass,
+        // Else skip
+        HideAll => false,
+    }
+}
+
+/// Display single line of test status
+///
+/// If span is given, that part of the word is highlighted (or marked on next line, without color)
+fn display_test(
+    (intent, word, pass): (bool, &str, bool),
+    reason: &str,
+    span: Option<&Range<usize>>,
+    max_word_len: usize,
+    no_color: bool,
+) {
+    let space = " ".repeat(max_word_len - word.chars().count());
+
+    if no_color {
+        println!(
+            " {intent} {word}{space}  {result} {reason}",
+            intent = if intent { "✔" } else { "✗" },
+            result = if pass { "pass" } else { "FAIL" },
+        );
+
+        // Mark span under word
+        if let Some(span) = span {
+            println!(
+                "   {space}{marker}",
+                space = " ".repeat(word[..span.start].chars().count()),
+                marker = "^".repeat(word[span.clone()].chars().count().max(1)),
+            );
+        }
+    } else {
+        // Underline span in word
+        let word = match span {
+            Some(span) => format!(
+                "{}\x1b[4;31m{}\x1b[0m{}",
+                &word[..span.start],
+                &word[span.clone()],
+                &word[span.end..],
+            ),
+            None => word.to_string(),
+        };
+
+        println!(
+            "  \x1b[{intent}\x1b[0m {word}{space}  \x1b[1;{result} \x1b[0;3;1m{reason}\x1b[0m",
+            intent = if intent { "36m✔" } else { "35m✗" },
+            result = if pass { "32mpass" } else { "31mFAIL" },
+        );
+    }
+}
+
 /// Reason for failure variants
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FailReason {
     /// Test passed, do not display reason
     Passed,
@@ -280,24 +477,25 @@ impl ValidStatus {
     }
 }
 
-/// Check if string is valid with rules
-pub fn validate_test(word: &str, rules: &Vec<Rule>) -> ValidStatus {
-    // Check for match with every rule, if not, return reason
-    for Rule {
-        intent,
-        pattern,
-        reason_ref,
-    } in rules
-    {
-        // Check if rule matches, and whether match signifies returning invalid or continuing
-        if intent
-            ^ pattern
-                .is_match(word)
-                .expect("Failed checking regex match. This error should NEVER APPEAR!")
-        {
-            return Invalid(*reason_ref);
-        }
+/// Get validity status from index of first rule that word does not follow
+fn status_of(failing: Option<usize>, scheme: &Phonet) -> ValidStatus {
+    match failing {
+        // Return reason of rule
+        Some(index) => Invalid(scheme.rules[index].reason_ref),
+        None => Valid,
+    }
+}
+
+/// Get byte range of first match of negative rule in string
+///
+/// Returns `None` for positive rules, as they fail by not matching
+pub(crate) fn match_span(word: &str, rule: &Rule) -> Option<Range<usize>> {
+    if rule.intent {
+        return None;
     }
 
-    Valid
+    rule.pattern
+        .find(word)
+        .expect("Failed checking regex match. This error should NEVER APPEAR!")
+        .map(|found| found.start()..found.end())
 }
@@ -0,0 +1,395 @@
+use super::{End, Kind, SyntaxTree};
+
+/// Line of formatted file
+struct Line {
+    /// Kind of statement, or `Blank` for blank line
+    kind: Kind,
+    /// Statement, without indentation
+    code: String,
+    /// Comment on same line as statement, starting with `#`
+    comment: Option<String>,
+}
+
+impl Line {
+    /// Create blank line
+    fn blank() -> Self {
+        Line {
+            kind: Kind::Blank,
+            code: String::new(),
+            comment: None,
+        }
+    }
+}
+
+/// Format file in canonical layout
+///
+/// Mode is put at the top of the file, followed by includes and classes. Rules and tests after a reason or note
+/// are indented by one and two levels. Spacing of statements is normalized, `⟨⟩` is replaced with
+/// `<>` where possible, and comments on the same line as a statement are aligned
+///
+/// Comments are kept, and the formatted file defines the same scheme. Multiline statements are
+/// joined onto a single line. Formatting a formatted file does not change it
+pub fn format_file(file: &str) -> String {
+    let lines = arrange(lines(&SyntaxTree::parse(file)));
+
+    // Indent rules and tests after the first reason or note
+    let mut grouped = false;
+    let mut indents: Vec<usize> = lines
+        .iter()
+        .map(|line| {
+            if matches!(line.kind, Kind::Reason | Kind::Note) {
+                grouped = true;
+            }
+            match line.kind {
+                Kind::Rule if grouped => 2,
+                Kind::Test if grouped => 4,
+                _ => 0,
+            }
+        })
+        .collect();
+
+    // Indent comments the same as the statement below
+    for i in (0..lines.len()).rev() {
+        if lines[i].kind == Kind::Comment {
+            indents[i] = match lines.get(i + 1) {
+                Some(next) if matches!(next.kind, Kind::Rule | Kind::Test | Kind::Comment) => {
+                    indents[i + 1]
+                }
+                _ => 0,
+            };
+        }
+    }
+
+    // Align comments after statements, except after mode
+    let width = lines
+        .iter()
+        .zip(&indents)
+        .filter(|(line, _)| line.comment.is_some() && line.kind != Kind::Mode)
+        .map(|(line, indent)| indent + line.code.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut output = String::new();
+    for (line, indent) in lines.iter().zip(indents) {
+        let code = " ".repeat(indent) + &line.code;
+
+        match &line.comment {
+            Some(comment) if line.kind == Kind::Mode => {
+                output += &format!("{code} ;{comment}");
+            }
+            Some(comment) => output += &format!("{code:width$} ;{comment}"),
+            None => output += &code,
+        }
+        output += "\n";
+    }
+
+    output
+}
+
+/// Get formatted lines of statements, without indentation
+///
+/// Each statement is put on its own line, with a comment after it on the same line of file.
+/// Repeated blank lines are joined
+fn lines(tree: &SyntaxTree) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+
+    // Whether current line of file has a statement, which comments are put after
+    let mut has_statement = false;
+    // Whether current line of file has only blank statements
+    let mut is_blank = true;
+
+    for node in &tree.nodes {
+        let text = node.text();
+        let kind = Kind::of(&text);
+
+        match kind {
+            Kind::Blank => {
+                if is_blank && node.end != End::Semicolon {
+                    lines.push(Line::blank());
+                }
+            }
+
+            // Comment after statement
+            Kind::Comment if has_statement => {
+                let comment = escape_multiline(text.trim());
+                if let Some(line) = lines.last_mut() {
+                    match &mut line.comment {
+                        Some(previous) => *previous += &format!("; {comment}"),
+                        None => line.comment = Some(comment),
+                    }
+                }
+            }
+
+            kind => {
+                lines.push(Line {
+                    kind,
+                    code: escape_multiline(&canonical(kind, &text)),
+                    comment: None,
+                });
+                has_statement = kind != Kind::Comment;
+            }
+        }
+
+        if kind != Kind::Blank {
+            is_blank = false;
+        }
+        if node.end != End::Semicolon {
+            has_statement = false;
+            is_blank = true;
+        }
+    }
+
+    lines
+}
+
+/// Move mode to top of file, and classes to the classes at the top of the file
+///
+/// Repeated blank lines are joined, and blank lines at start and end are removed
+fn arrange(lines: Vec<Line>) -> Vec<Line> {
+    let (modes, mut lines): (Vec<_>, Vec<_>) =
+        lines.into_iter().partition(|line| line.kind == Kind::Mode);
+
+    // Header is includes, classes, and comments before any other statement
+    let header_len = lines
+        .iter()
+        .position(|line| {
+            !matches!(
+                line.kind,
+                Kind::Include | Kind::Class | Kind::Comment | Kind::Blank
+            )
+        })
+        .unwrap_or(lines.len());
+    let body = lines.split_off(header_len);
+    let (classes, body): (Vec<_>, Vec<_>) =
+        body.into_iter().partition(|line| line.kind == Kind::Class);
+
+    // Add classes after last class of header, or at top
+    if !classes.is_empty() {
+        let index = lines
+            .iter()
+            .rposition(|line| line.kind == Kind::Class)
+            .map_or(0, |i| i + 1);
+        if index == 0 {
+            lines.insert(0, Line::blank());
+        }
+        lines.splice(index..index, classes);
+    }
+    lines.extend(body);
+
+    if !modes.is_empty() {
+        lines.splice(0..0, modes.into_iter().chain([Line::blank()]));
+    }
+
+    // Remove repeated blank lines, and blank lines at start and end
+    let mut arranged: Vec<Line> = Vec::new();
+    for line in lines {
+        let blank = line.kind == Kind::Blank;
+        if blank && arranged.last().is_none_or(|last| last.kind == Kind::Blank) {
+            continue;
+        }
+        arranged.push(line);
+    }
+    if arranged.last().is_some_and(|line| line.kind == Kind::Blank) {
+        arranged.pop();
+    }
+
+    arranged
+}
+
+/// Get statement with canonical spacing
+///
+/// `text` is text of statement, as used by parser
+fn canonical(kind: Kind, text: &str) -> String {
+    let text = text.trim();
+    let mut chars = text.chars();
+    let first = chars.next().unwrap_or_default();
+    let rest = chars.as_str();
+
+    // Join operator and value with a space, if value is not empty
+    let join = |operator: &str, value: &str| {
+        if value.is_empty() {
+            operator.to_string()
+        } else {
+            format!("{operator} {value}")
+        }
+    };
+
+    match kind {
+        Kind::Blank | Kind::Comment => text.to_string(),
+
+        Kind::Mode => format!("~{}", rest.replace(' ', "")),
+
+        Kind::Include => join("<", rest.trim()),
+
+        Kind::Class => match rest.split_once('=') {
+            Some((name, value)) => format!(
+                "${} = {}",
+                name.trim(),
+                normalize_brackets(&single_spaces(value))
+            ),
+            None => single_spaces(text),
+        },
+
+        Kind::Weights => join("%", &rest.split_whitespace().collect::<Vec<_>>().join(" ")),
+
+        Kind::Syllable | Kind::Rule => join(
+            &first.to_string(),
+            &normalize_brackets(&single_spaces(rest)),
+        ),
+
+        Kind::Test => {
+            let mut chars = rest.trim_start_matches(' ').chars();
+            match chars.next() {
+                Some(intent @ ('+' | '!')) => join(
+                    &format!("?{intent}"),
+                    &chars
+                        .as_str()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                _ => single_spaces(text),
+            }
+        }
+
+        Kind::Reason => match rest.trim_start_matches(' ').strip_prefix('*') {
+            Some(note) => join("@*", note.trim()),
+            None => join("@", rest.trim()),
+        },
+
+        Kind::Note => join("*", rest.trim()),
+
+        Kind::Unknown => single_spaces(text),
+    }
+}
+
+/// Replace each run of spaces with a single space, and remove spaces at start and end
+///
+/// Other whitespace is kept, as it is not ignored in patterns
+fn single_spaces(s: &str) -> String {
+    s.split(' ')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Keep literal `&` characters, which would otherwise start a multiline statement
+///
+/// Statement is made multiline with an extra `&` character, and ended with a semicolon
+pub(crate) fn escape_multiline(s: &str) -> String {
+    match s.find('&') {
+        Some(index) => format!("{}&{};", &s[..index], &s[index..]),
+        None => s.to_string(),
+    }
+}
+
+/// Replace `⟨` and `⟩` with `<` and `>` respectively, for classes
+///
+/// Brackets are only replaced if they would still be parsed as a class, not as a look-behind or
+/// named group
+pub(crate) fn normalize_brackets(pattern: &str) -> String {
+    let mut output = String::new();
+    // Pattern before current character, without spaces, as seen by parser
+    let mut parsed = String::new();
+    // Whether an ascii `<` is not closed yet, which would include the next class
+    let mut open = false;
+
+    let mut rest = pattern;
+    while let Some(ch) = rest.chars().next() {
+        rest = &rest[ch.len_utf8()..];
+
+        let not_group =
+            !(parsed.ends_with("(?") || parsed.ends_with("(?P") || parsed.ends_with("\\k"));
+
+        match ch {
+            '⟨' if !open && not_group => {
+                let name = rest.split_once('⟩').map(|(name, _)| name).filter(|name| {
+                    !name.is_empty() && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+                });
+
+                if let Some(name) = name {
+                    output += &format!("<{name}>");
+                    parsed += &format!("<{name}>");
+                    rest = &rest[name.len() + '⟩'.len_utf8()..];
+                    continue;
+                }
+            }
+
+            '<' if not_group => open = true,
+            '>' => open = false,
+            _ => (),
+        }
+
+        output.push(ch);
+        if ch != ' ' {
+            parsed.push(ch);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_brackets_works() {
+        assert_eq!(normalize_brackets("⟨C⟩ ⟨V⟩"), "<C> <V>");
+        assert_eq!(normalize_brackets("(?<x> .) \\k<x>"), "(?<x> .) \\k<x>");
+        assert_eq!(normalize_brackets("( ?⟨C⟩ )"), "( ?⟨C⟩ )");
+        assert_eq!(normalize_brackets("(?<=a) ⟨C⟩"), "(?<=a) <C>");
+        assert_eq!(normalize_brackets("a < ⟨C⟩"), "a < ⟨C⟩");
+        assert_eq!(normalize_brackets("⟨not a class⟩"), "⟨not a class⟩");
+    }
+
+    #[test]
+    fn format_file_works() {
+        let file = [
+            "# Classes",
+            "$_=[ptkaeiou];$C  =  [ptk]    ;# Consonants",
+            "",
+            "",
+            "@*   Syllables",
+            "+ ^ ( ⟨C⟩   ⟨V⟩ )+ $ ;# Structure",
+            "?+   taka &",
+            "    tika",
+            "  kupi;",
+            "~<> ;# Mode",
+            "  * Some tests",
+            "  # Should fail",
+            "?! tk",
+            "$V = [aeiou]",
+        ]
+        .join("\n");
+
+        let expected = [
+            "~<> ;# Mode",
+            "",
+            "# Classes",
+            "$_ = [ptkaeiou]",
+            "$C = [ptk]           ;# Consonants",
+            "$V = [aeiou]",
+            "",
+            "@* Syllables",
+            "  + ^ ( <C> <V> )+ $ ;# Structure",
+            "    ?+ taka tika kupi",
+            "* Some tests",
+            "    # Should fail",
+            "    ?! tk",
+            "",
+        ]
+        .join("\n");
+
+        let formatted = format_file(&file);
+        assert_eq!(formatted, expected);
+        assert_eq!(format_file(&formatted), formatted);
+    }
+
+    #[test]
+    fn format_file_keeps_multiline_characters() {
+        let formatted = format_file("?+ a && b;# c && d;\n* Note");
+        assert_eq!(formatted, "?+ a && b; ;# c && d;\n* Note\n");
+        assert_eq!(format_file(&formatted), formatted);
+    }
+}
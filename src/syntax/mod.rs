@@ -0,0 +1,323 @@
+/// Format file in canonical layout
+mod format;
+
+use std::{fmt::Display, mem};
+
+use crate::types::Span;
+
+pub use format::format_file;
+pub(crate) use format::{escape_multiline, normalize_brackets};
+
+/// Lossless syntax tree of file
+///
+/// Every character of the file is kept, including comments, whitespace, and the spelling of each
+/// statement. Displaying the tree gives the exact text of the file it was parsed from
+///
+/// Statements can be changed, added, or removed, then the tree can be displayed as a new file
+///
+/// ```
+/// use phonet::{End, Kind, Node, SyntaxTree};
+///
+/// let mut tree = SyntaxTree::parse("$C = [ptk] ;# Consonants\n+ ^ <C>+ $\n");
+/// assert_eq!(tree.to_string(), "$C = [ptk] ;# Consonants\n+ ^ <C>+ $\n");
+///
+/// // Rename class
+/// tree.nodes[0].raw = "$Cons = [ptk] ".to_string();
+/// tree.nodes[2].raw = "+ ^ <Cons>+ $".to_string();
+/// // Add test after rule
+/// tree.nodes.insert(3, Node::new("?+ pa", End::Linebreak));
+///
+/// assert_eq!(tree.nodes[3].kind(), Kind::Test);
+/// assert_eq!(tree.to_string(), "$Cons = [ptk] ;# Consonants\n+ ^ <Cons>+ $\n?+ pa\n");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxTree {
+    /// Statements of file, in order, including comments and blank statements
+    ///
+    /// A statement after a comment on the same line, such as `;# Comment`, is a separate comment
+    /// statement
+    pub nodes: Vec<Node>,
+}
+
+/// Statement of file, as written
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// Text of statement, exactly as written in file
+    ///
+    /// Includes surrounding whitespace, and the `&` character and linebreaks of multiline
+    /// statements
+    pub raw: String,
+    /// Position of statement in file that tree was parsed from
+    ///
+    /// Only the part on the first line of statement is covered. Line number 0 is used for
+    /// statements which were not parsed from a file
+    pub span: Span,
+    /// Character which ends statement
+    pub end: End,
+}
+
+/// Kind of statement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Empty, or only whitespace
+    Blank,
+    /// Comment, starting with `#`
+    Comment,
+    /// Mode, starting with `~`
+    Mode,
+    /// Include of another file, starting with `<`
+    Include,
+    /// Class definition, starting with `$`
+    Class,
+    /// Weights of segments, starting with `%`
+    Weights,
+    /// Syllable template, starting with `=`
+    Syllable,
+    /// Rule, starting with `+` or `!`
+    Rule,
+    /// Tests, starting with `?`
+    Test,
+    /// Reason, starting with `@`
+    Reason,
+    /// Note, starting with `*`
+    Note,
+    /// Unknown line operator
+    Unknown,
+}
+
+/// Character which ends statement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum End {
+    /// Linebreak (`\n`)
+    Linebreak,
+    /// Semicolon (`;`)
+    Semicolon,
+    /// End of file
+    Eof,
+}
+
+impl SyntaxTree {
+    /// Parse file into syntax tree
+    ///
+    /// This never fails, as every statement is kept as written
+    pub fn parse(file: &str) -> Self {
+        let mut nodes = Vec::new();
+
+        // Building statement, and position of first character
+        let mut raw = String::new();
+        let mut start = Span {
+            line: 1,
+            column: 1,
+            len: 0,
+        };
+        // Whether statement is multiline (after '&' character)
+        let mut multiline = false;
+
+        let (mut line, mut column) = (1, 1);
+
+        for ch in file.chars() {
+            let end = match ch {
+                // Linebreaks are ignored in multiline
+                '\n' if !multiline => Some(End::Linebreak),
+                ';' => Some(End::Semicolon),
+                _ => None,
+            };
+
+            match end {
+                Some(end) => {
+                    nodes.push(Node::parsed(mem::take(&mut raw), start, end));
+                    multiline = false;
+                }
+                None => {
+                    if ch == '&' {
+                        multiline = true;
+                    }
+                    raw.push(ch);
+                }
+            }
+
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+
+            // Next statement starts after end character
+            if end.is_some() {
+                start = Span {
+                    line,
+                    column,
+                    len: 0,
+                };
+            }
+        }
+
+        nodes.push(Node::parsed(raw, start, End::Eof));
+
+        Self { nodes }
+    }
+}
+
+impl Display for SyntaxTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in &self.nodes {
+            write!(f, "{node}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Node {
+    /// Display raw text of statement, with character which ends it
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)?;
+        match self.end {
+            End::Linebreak => writeln!(f),
+            End::Semicolon => write!(f, ";"),
+            End::Eof => Ok(()),
+        }
+    }
+}
+
+impl Node {
+    /// Create statement from raw text, which was not parsed from a file
+    ///
+    /// Position of statement is unknown
+    pub fn new(raw: impl Into<String>, end: End) -> Self {
+        Self {
+            raw: raw.into(),
+            span: Span::default(),
+            end,
+        }
+    }
+
+    /// Create statement from raw text, with position of first character in file
+    fn parsed(raw: String, start: Span, end: End) -> Self {
+        let len = raw
+            .split('\n')
+            .next()
+            .map_or(0, |line| line.chars().count());
+        Self {
+            raw,
+            span: Span { len, ..start },
+            end,
+        }
+    }
+
+    /// Get kind of statement
+    pub fn kind(&self) -> Kind {
+        Kind::of(&self.text())
+    }
+
+    /// Get text of statement, as used by parser
+    ///
+    /// Multiline statements are treated as single line, with linebreaks and first `&` character
+    /// removed
+    pub fn text(&self) -> String {
+        self.chars().map(|(ch, _)| ch).collect()
+    }
+
+    /// Get each character of text of statement, with position in file
+    ///
+    /// Positions are only known for statements parsed from a file
+    pub fn chars(&self) -> impl Iterator<Item = (char, Span)> + '_ {
+        let (mut line, mut column) = (self.span.line, self.span.column);
+        let mut multiline = false;
+
+        self.raw.chars().filter_map(move |ch| {
+            let position = Span {
+                line,
+                column,
+                len: 1,
+            };
+
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+                return None;
+            }
+            column += 1;
+
+            // First '&' character starts multiline
+            if ch == '&' && !multiline {
+                multiline = true;
+                return None;
+            }
+
+            Some((ch, position))
+        })
+    }
+}
+
+impl Kind {
+    /// Get kind of statement from text
+    pub fn of(text: &str) -> Self {
+        match text.trim().chars().next() {
+            None => Kind::Blank,
+            Some('#') => Kind::Comment,
+            Some('~') => Kind::Mode,
+            Some('<') => Kind::Include,
+            Some('$') => Kind::Class,
+            Some('%') => Kind::Weights,
+            Some('=') => Kind::Syllable,
+            Some('+' | '!') => Kind::Rule,
+            Some('?') => Kind::Test,
+            Some('@') => Kind::Reason,
+            Some('*') => Kind::Note,
+            Some(_) => Kind::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syntax_tree_is_lossless() {
+        let files = [
+            "",
+            "\n\n",
+            "~<> ;# Mode\n$_ = [ab]\r\n\n+ ^ <_>+ $;;\n?+ a b &\n  c\n  d;# Comment\n* Note",
+            "?! &\n  a\n\n  b",
+            "a & b & c ; d",
+        ];
+
+        for file in files {
+            assert_eq!(SyntaxTree::parse(file).to_string(), file);
+        }
+    }
+
+    #[test]
+    fn syntax_tree_works() {
+        let tree = SyntaxTree::parse("$C = [ptk] ;# Consonants\n?+ &\n  pa\n  ka;");
+
+        let kinds: Vec<_> = tree.nodes.iter().map(Node::kind).collect();
+        assert_eq!(
+            kinds,
+            vec![Kind::Class, Kind::Comment, Kind::Test, Kind::Blank,]
+        );
+
+        let node = &tree.nodes[2];
+        assert_eq!(node.raw, "?+ &\n  pa\n  ka");
+        assert_eq!(node.text(), "?+   pa  ka");
+        assert_eq!(node.end, End::Semicolon);
+        assert_eq!(
+            node.span,
+            Span {
+                line: 2,
+                column: 1,
+                len: 4
+            }
+        );
+        assert_eq!(
+            tree.nodes[1].span,
+            Span {
+                line: 1,
+                column: 13,
+                len: 12
+            }
+        );
+    }
+}
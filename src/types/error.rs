@@ -1,60 +1,350 @@
 use snafu::prelude::*;
 
+use super::Span;
+
 /// Error enum for `Phonet` struct in `parse.rs`
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display(
-        "Unknown intent identifier `{ch}`. Must be either `+` or `!`, at line {line}"
+        "Unknown intent identifier `{ch}`. Must be either `+` or `!`, at line {}",
+        span.line
+    ))]
+    UnknownIntentIdentifier { ch: char, span: Span },
+
+    #[snafu(display("Unknown line operator `{ch}`, at line {}", span.line))]
+    UnknownLineOperator { ch: char, span: Span },
+
+    #[snafu(display("Mode already defined, at line {}", span.line))]
+    ModeAlreadyDefined { span: Span },
+
+    #[snafu(display(
+        "Mode is invalid, it must be one of `<>`, `//`, or `[]`, at line {}",
+        span.line
     ))]
-    UnknownIntentIdentifier { ch: char, line: usize },
+    InvalidMode { span: Span },
 
-    #[snafu(display("Unknown line operator `{ch}`, at line {line}"))]
-    UnknownLineOperator { ch: char, line: usize },
+    #[snafu(display("No class name given, at line {}", span.line))]
+    NoClassName { span: Span },
+
+    #[snafu(display(
+        "Invalid class name `{name}`, on {}. Must only contain characters from [a-zA-Z0-9_]",
+        span.line
+    ))]
+    InvalidClassName { name: String, span: Span },
 
-    #[snafu(display("Mode already defined, at line {line}"))]
-    ModeAlreadyDefined { line: usize },
+    #[snafu(display("Class already exists with `{name}`, on {}", span.line))]
+    ClassAlreadyExist { name: String, span: Span },
 
-    #[snafu(display("Mode is invalid, it must be one of `<>`, `//`, or `[]`, at line {line}"))]
-    InvalidMode { line: usize },
+    #[snafu(display("No class value given, with name `{name}`, at line {}", span.line))]
+    NoClassValue { name: String, span: Span },
 
-    #[snafu(display("No class name given, at line {line}"))]
-    NoClassName { line: usize },
+    #[snafu(display(
+        "Invalid weight `{value}`. Must be a segment and a non-negative number, separated by a colon (`:`), at line {}",
+        span.line
+    ))]
+    InvalidWeight { value: String, span: Span },
 
     #[snafu(display(
-        "Invalid class name `{name}`, on {line}. Must only contain characters from [a-zA-Z0-9_]"
+        "Weight already defined for segment `{segment}`, at line {}",
+        span.line
     ))]
-    InvalidClassName { name: String, line: usize },
+    WeightAlreadyDefined { segment: String, span: Span },
+
+    #[snafu(display("Invalid syllable template: {reason}, at line {}", span.line))]
+    InvalidSyllable { reason: String, span: Span },
 
-    #[snafu(display("Class already exists with `{name}`, on {line}"))]
-    ClassAlreadyExist { name: String, line: usize },
+    #[snafu(display("Failed to parse Regex: {err}, at line {}", span.line))]
+    RegexFail { err: fancy_regex::Error, span: Span },
 
-    #[snafu(display("No class value given, with name `{name}`, at line {line}"))]
-    NoClassValue { name: String, line: usize },
+    #[snafu(display("Class not found, with name `{name}`, at line {}", span.line))]
+    ClassNotFound {
+        name: String,
+        span: Span,
+        /// Name of defined class with similar name
+        suggestion: Option<String>,
+    },
 
-    #[snafu(display("Failed to parse Regex: {err}, at line {line}"))]
-    RegexFail {
-        err: fancy_regex::Error,
-        line: usize,
+    #[snafu(display(
+        "Circular class reference `{}`, with classes defined at lines {}",
+        path.join(" -> "),
+        display_lines(lines),
+    ))]
+    CircularClass {
+        /// Names of classes in cycle, starting and ending with the same class
+        path: Vec<String>,
+        /// Line number of definition of each class in cycle
+        lines: Vec<usize>,
+        /// Position of class name which completes the cycle
+        span: Span,
     },
 
-    #[snafu(display("Class not found, with name `{name}`, at line {line}"))]
-    ClassNotFound { name: String, line: usize },
+    #[snafu(display(
+        "Unexpected class name opening bracket (`<`), in pattern `{pattern}`, at line {}",
+        span.line
+    ))]
+    ClassUnexpectedOpenName { pattern: String, span: Span },
 
     #[snafu(display(
-        "Unexpected class name opening bracket (`<`), in pattern `{pattern}`, at line {line}"
+        "Unexpected class name closing bracket (`>`), in pattern `{pattern}`, at line {}",
+        span.line
     ))]
-    ClassUnexpectedOpenName { pattern: String, line: usize },
+    ClassUnexpectedCloseName { pattern: String, span: Span },
 
     #[snafu(display(
-        "Unexpected class name closing bracket (`>`), in pattern `{pattern}`, at line {line}"
+        "Class name was not closed with bracket (`>`) before end of pattern, in pattern `{pattern}`, at line {}",
+        span.line
     ))]
-    ClassUnexpectedCloseName { pattern: String, line: usize },
+    ClassUnexpectedEnd { pattern: String, span: Span },
+
+    #[snafu(display("Could not read file `{path}`: {reason}"))]
+    ReadFail { path: String, reason: String },
 
     #[snafu(display(
-    "Class name was not closed with bracket (`>`) before end of pattern, in pattern `{pattern}`, at line {line}"
-  ))]
-    ClassUnexpectedEnd { pattern: String, line: usize },
+        "Could not include file `{path}`: {reason}, at line {}",
+        span.line
+    ))]
+    IncludeFail {
+        path: String,
+        reason: String,
+        span: Span,
+    },
+
+    #[snafu(display("Circular include `{}`, at line {}", path.join(" -> "), span.line))]
+    CircularInclude {
+        /// Paths of files in cycle, starting and ending with the same file
+        path: Vec<String>,
+        span: Span,
+    },
+
+    #[snafu(display("{source}, in file `{path}` included at line {}", span.line))]
+    InFile {
+        /// Path of included file
+        path: String,
+        /// Text of included file, for displaying line of error
+        text: String,
+        /// Position of include statement
+        span: Span,
+        /// Error in included file
+        source: Box<Error>,
+    },
 
     #[snafu(display("No 'any' class was defined. Define with `$_ = ...`"))]
     MissingAnyClass,
+
+    #[snafu(display("No syllable templates were defined. Define with `= ...`"))]
+    MissingSyllables,
+
+    #[snafu(display("Cannot generate words from class `{name}`: {reason}"))]
+    InvalidInventory { name: String, reason: String },
+
+    #[snafu(display(
+        "Range of word lengths `{start}..{end}` is empty. Maximum is exclusive, so it must be greater than minimum"
+    ))]
+    EmptyLengthRange { start: usize, end: usize },
+
+    #[snafu(display(
+        "Generation stopped after {attempts} attempts, with {} of {requested} words generated{}",
+        words.len(),
+        display_rejected_by(rejected_by, reason),
+    ))]
+    GenerationExhausted {
+        /// Amount of words requested
+        requested: usize,
+        /// Amount of candidate words tried, for all words together
+        attempts: usize,
+        /// Words generated before stopping
+        words: Vec<String>,
+        /// Line number of rule which rejected the most candidates
+        rejected_by: Option<usize>,
+        /// Reason of rule which rejected the most candidates
+        reason: Option<String>,
+    },
+}
+
+/// Format list of line numbers, separated by commas
+fn display_lines(lines: &[usize]) -> String {
+    lines
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format rule which rejected the most generated candidates
+fn display_rejected_by(line: &Option<usize>, reason: &Option<String>) -> String {
+    match (line, reason) {
+        (Some(line), Some(reason)) => {
+            format!(". Most candidates were rejected by rule at line {line} ({reason})")
+        }
+        (Some(line), None) => format!(". Most candidates were rejected by rule at line {line}"),
+        _ => String::new(),
+    }
+}
+
+impl Error {
+    /// Get position of error in file, if known
+    ///
+    /// For an error in an included file, this is the position of the include statement
+    pub fn span(&self) -> Option<Span> {
+        use Error::*;
+
+        let span = match self {
+            UnknownIntentIdentifier { span, .. }
+            | UnknownLineOperator { span, .. }
+            | ModeAlreadyDefined { span }
+            | InvalidMode { span }
+            | NoClassName { span }
+            | InvalidClassName { span, .. }
+            | ClassAlreadyExist { span, .. }
+            | NoClassValue { span, .. }
+            | InvalidWeight { span, .. }
+            | WeightAlreadyDefined { span, .. }
+            | InvalidSyllable { span, .. }
+            | RegexFail { span, .. }
+            | ClassNotFound { span, .. }
+            | CircularClass { span, .. }
+            | ClassUnexpectedOpenName { span, .. }
+            | ClassUnexpectedCloseName { span, .. }
+            | ClassUnexpectedEnd { span, .. }
+            | IncludeFail { span, .. }
+            | CircularInclude { span, .. }
+            | InFile { span, .. } => span,
+
+            ReadFail { .. }
+            | MissingAnyClass
+            | MissingSyllables
+            | InvalidInventory { .. }
+            | EmptyLengthRange { .. }
+            | GenerationExhausted { .. } => return None,
+        };
+
+        // Line 0 is unknown position
+        (span.line > 0).then_some(*span)
+    }
+
+    /// Get hint to fix error, if any
+    pub fn hint(&self) -> Option<String> {
+        use Error::*;
+
+        Some(match self {
+            ClassNotFound {
+                suggestion: Some(suggestion),
+                ..
+            } => format!("did you mean `${suggestion}`?"),
+
+            UnknownLineOperator { .. } => {
+                "statements must start with one of `#`, `~`, `<`, `$`, `%`, `=`, `+`, `!`, `?`, `@`, or `*`"
+                    .to_string()
+            }
+
+            UnknownIntentIdentifier { .. } => {
+                "use `?+` for valid words, or `?!` for invalid words".to_string()
+            }
+
+            InvalidClassName { .. } => {
+                "class names can only contain letters, numbers, and underscores".to_string()
+            }
+
+            CircularClass { .. } => {
+                "a class cannot use itself, directly or through other classes".to_string()
+            }
+
+            ClassUnexpectedOpenName { .. } | ClassUnexpectedEnd { .. } => {
+                "class names are written as `<Name>`".to_string()
+            }
+
+            CircularInclude { .. } => {
+                "a file cannot include itself, directly or through other files".to_string()
+            }
+
+            InFile { source, .. } => return source.hint(),
+
+            _ => return None,
+        })
+    }
+
+    /// Format error with line of source file, marking position of error, and hint
+    ///
+    /// `source` should be the file that was parsed. Errors in included files are shown with the
+    /// line of the included file
+    pub fn render(&self, source: &str, no_color: bool) -> String {
+        // Find error in innermost included file
+        let (mut error, mut source) = (self, source);
+        while let Error::InFile {
+            text,
+            source: inner,
+            ..
+        } = error
+        {
+            error = inner;
+            source = text;
+        }
+
+        render_diagnostic(
+            ("error", "31;1"),
+            &self.to_string(),
+            error.span(),
+            error.hint().as_deref(),
+            source,
+            no_color,
+        )
+    }
+}
+
+/// Format message with line of source file, marking position in line, and hint
+///
+/// `label` is the kind of message (such as `error`), with ANSI color code for label and marker
+pub(crate) fn render_diagnostic(
+    label: (&str, &str),
+    message: &str,
+    span: Option<Span>,
+    hint: Option<&str>,
+    source: &str,
+    no_color: bool,
+) -> String {
+    // Colors, or empty if no color
+    let color = |code: &str| {
+        if no_color {
+            String::new()
+        } else {
+            format!("\x1b[{code}m")
+        }
+    };
+    let (label, accent) = (label.0, color(label.1));
+    let (blue, yellow, reset) = (color("34"), color("33"), color("0"));
+
+    let mut output = format!("{accent}{label}{reset}: {message}\n");
+
+    // Line of source, if position is known
+    let line = span
+        .filter(|span| span.line > 0)
+        .and_then(|span| Some((span, source.lines().nth(span.line - 1)?)));
+
+    let Some((span, text)) = line else {
+        if let Some(hint) = hint {
+            output += &format!("  {yellow}hint{reset}: {hint}\n");
+        }
+        return output;
+    };
+
+    let number = span.line.to_string();
+    let gutter = " ".repeat(number.len());
+
+    // Indent marker to column, keeping tabs so it lines up
+    let indent: String = text
+        .chars()
+        .take(span.column.saturating_sub(1))
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect();
+    let marker = "^".repeat(span.len.max(1));
+
+    output += &format!("{blue}{gutter} |{reset}\n");
+    output += &format!("{blue}{number} |{reset} {text}\n");
+    output += &format!("{blue}{gutter} |{reset} {indent}{accent}{marker}{reset}");
+    if let Some(hint) = hint {
+        output += &format!(" {yellow}{hint}{reset}");
+    }
+    output += "\n";
+
+    output
 }
@@ -1,13 +1,15 @@
 /// Holds error type
 mod error;
 
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, ops::Range};
 
 use clap::{builder::PossibleValue, ValueEnum};
 use fancy_regex::Regex;
+use serde::Serialize;
 
+pub(crate) use error::render_diagnostic;
 pub use error::Error;
-pub use crate::run::FailReason;
+pub use crate::run::{FailReason, Violation};
 
 use DisplayLevel::*;
 
@@ -16,11 +18,94 @@ pub struct Rule {
     pub intent: bool,
     pub pattern: Regex,
     pub reason_ref: Option<usize>,
+    /// Line number of rule definition
+    pub line: usize,
+    /// Path of file which rule was defined in, if rule was included from another file
+    pub file: Option<String>,
+}
+
+/// Position of text in file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    /// Line number, starting at 1
+    ///
+    /// Line number 0 is used for unknown position
+    pub line: usize,
+    /// Column of first character in line, in characters, starting at 1
+    pub column: usize,
+    /// Length of text, in characters
+    pub len: usize,
+}
+
+impl Span {
+    /// Get span covering list of spans, in order
+    ///
+    /// Only the part on the line of the first span is covered
+    pub(crate) fn covering(spans: &[Span]) -> Span {
+        let Some(first) = spans.first() else {
+            return Span::default();
+        };
+
+        let last = spans
+            .iter()
+            .take_while(|span| span.line == first.line)
+            .last()
+            .unwrap_or(first);
+
+        Span {
+            len: last.column + last.len - first.column,
+            ..*first
+        }
+    }
+
+    /// Get span of range of characters, from position of each character
+    ///
+    /// If range is outside of positions, the span covering all positions is used
+    pub(crate) fn locate(positions: &[Span], range: Range<usize>) -> Span {
+        Span::covering(positions.get(range).unwrap_or(positions))
+    }
+}
+
+/// Template for generating a syllable
+#[derive(Clone, Debug)]
+pub struct Syllable {
+    /// Parts of syllable, in order
+    pub parts: Vec<SyllablePart>,
+    /// Line number of template definition
+    pub line: usize,
+}
+
+/// Part of a syllable template
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyllablePart {
+    /// Any segment of a class
+    Class(String),
+    /// Literal character
+    Literal(String),
+    /// Sequence of parts
+    Group(Vec<SyllablePart>),
+    /// Part which may be left out
+    Optional(Box<SyllablePart>),
+}
+
+impl SyllablePart {
+    /// Add names of all classes used in part to list
+    pub fn class_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Class(name) => names.push(name),
+            Self::Literal(_) => (),
+            Self::Group(parts) => parts.iter().for_each(|part| part.class_names(names)),
+            Self::Optional(part) => part.class_names(names),
+        }
+    }
 }
 
 /// Alias for hashmap of class name and value
 pub type Classes = HashMap<String, String>;
 
+/// Alias for hashmap of segment and relative weight
+pub type Weights = HashMap<String, f64>;
+
 /// Definition of test or note
 #[derive(Debug)]
 pub enum TestDefinition {
@@ -36,6 +121,8 @@ pub enum TestDefinition {
 }
 
 /// Result of test or note
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TestResult {
     /// Display line of text
     Note(String),
@@ -49,6 +136,21 @@ pub enum TestResult {
         pass: bool,
         /// Reason for fail
         reason: FailReason,
+        /// Byte range of word which matched negative rule, if failed
+        span: Option<Range<usize>>,
+    },
+    /// Result of test, with every rule that word does not follow
+    ///
+    /// Created when running with `RunOptions::all_violations`
+    Detailed {
+        /// Intent of test passing
+        intent: bool,
+        /// Word tested
+        word: String,
+        /// Whether test passed or not
+        pass: bool,
+        /// Rules which word does not follow, in order of definition
+        violations: Vec<Violation>,
     },
 }
 
@@ -104,6 +206,48 @@ impl Default for DisplayLevel {
     }
 }
 
+/// Format of test results, for `Results::format` method
+#[derive(Clone, Copy, Default)]
+pub enum OutputFormat {
+    /// Human-readable text, using `Results::display`
+    #[default]
+    Human,
+    /// JSON object
+    Json,
+    /// Test Anything Protocol
+    Tap,
+    /// JUnit XML
+    Junit,
+}
+
+// Custom implementation, for argument aliases
+impl ValueEnum for OutputFormat {
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        // `help` values must mirror comments
+        Some(match self {
+            Self::Human => PossibleValue::new("human")
+                .aliases(["h", "text"])
+                .help("Human-readable text"),
+
+            Self::Json => PossibleValue::new("json")
+                .aliases(["j"])
+                .help("JSON object"),
+
+            Self::Tap => PossibleValue::new("tap")
+                .aliases(["t"])
+                .help("Test Anything Protocol"),
+
+            Self::Junit => PossibleValue::new("junit")
+                .aliases(["x", "xml"])
+                .help("JUnit XML"),
+        })
+    }
+
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Human, Self::Json, Self::Tap, Self::Junit]
+    }
+}
+
 impl Display for DisplayLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
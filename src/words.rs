@@ -0,0 +1,118 @@
+// * This file is just for the binary
+
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+/// Read words of word list from file, or standard input if path is not given or `-`
+///
+/// `column` starts at 1, for a column of a TSV or CSV file. If `header` is `true`, the first line
+/// is skipped
+pub fn read_words(
+    input: Option<&str>,
+    column: Option<usize>,
+    header: bool,
+) -> Result<Vec<String>, String> {
+    if column == Some(0) {
+        return Err("Column of word list starts at 1".to_string());
+    }
+
+    let text = match input {
+        None | Some("-") => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("Could not read word list from standard input: {err}"))?;
+            text
+        }
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("Could not read word list `{path}`: {err}"))?,
+    };
+
+    Ok(parse_words(&text, column, header))
+}
+
+/// Get words from text of word list, one per line
+///
+/// Whitespace around each word is removed, and blank words are skipped. Lines without a cell in
+/// `column` are skipped
+fn parse_words(text: &str, column: Option<usize>, header: bool) -> Vec<String> {
+    text.lines()
+        .skip(usize::from(header))
+        .filter_map(|line| match column {
+            Some(column) => split_cells(line).into_iter().nth(column - 1),
+            None => Some(line.to_string()),
+        })
+        .map(|word| word.trim().to_string())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Split line of TSV or CSV file into cells
+///
+/// Cells are separated by tabs if line has a tab, otherwise by commas. Cells can be quoted with
+/// `"`, using `""` for a quote inside a quoted cell
+fn split_cells(line: &str) -> Vec<String> {
+    let delimiter = if line.contains('\t') { '\t' } else { ',' };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted => {
+                // Escaped quote
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            ch if ch == delimiter && !quoted => cells.push(std::mem::take(&mut cell)),
+            ch => cell.push(ch),
+        }
+    }
+    cells.push(cell);
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_cells_works() {
+        assert_eq!(split_cells("taso\tnoun"), vec!["taso", "noun"]);
+        assert_eq!(split_cells("taso,noun"), vec!["taso", "noun"]);
+        assert_eq!(
+            split_cells(r#"taso,"to eat, to drink","a ""b""""#),
+            vec!["taso", "to eat, to drink", r#"a "b""#]
+        );
+        assert_eq!(split_cells("a, b\tc"), vec!["a, b", "c"]);
+        assert_eq!(split_cells(""), vec![""]);
+    }
+
+    #[test]
+    fn parse_words_works() {
+        assert_eq!(
+            parse_words("taso\n\n  kuli \n", None, false),
+            vec!["taso", "kuli"]
+        );
+
+        let text = "word,meaning\ntaso,\"to eat, to drink\"\n,empty\nkuli";
+        assert_eq!(parse_words(text, Some(1), true), vec!["taso", "kuli"]);
+        assert_eq!(
+            parse_words(text, Some(2), true),
+            vec!["to eat, to drink", "empty"]
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use phonet::{self, Phonet};
+use phonet::{
+    self, format_file, format_files, CapacityCount, CapacityOptions, End, Error, GenerateOptions,
+    Kind, Lint, Node, OutputFormat, Phonet, RunOptions, Span, Syllable, SyllablePart, SyntaxTree,
+    TestDefinition, TestResult, Violation,
+};
 
 #[test]
 fn tokipona_should_pass() {
@@ -63,3 +67,1173 @@ fn invalid_syntax() {
         "Syntax should not have parsed, but did"
     );
 }
+
+#[test]
+fn generate_uses_segments() {
+    let scheme = Phonet::parse("$_ = (?: [ptk] | sh | [ai] ); + ^ ( (?:[ptk]|sh) [ai] )+ $")
+        .expect("Failed to parse");
+
+    let words = scheme.generate(20, 2..9).expect("Failed to generate");
+    assert_eq!(words.len(), 20);
+
+    for word in words {
+        assert!(
+            !word.contains(['(', '?', ':', '[', '|']),
+            "Generated word `{word}` contains regex syntax"
+        );
+    }
+
+    assert!(
+        Phonet::parse("$_ = [a-z]+")
+            .unwrap()
+            .generate(1, 1..2)
+            .is_err(),
+        "Unbounded class should not generate"
+    );
+}
+
+#[test]
+fn generate_from_nested_class_sets() {
+    // Inventory is `[<C><V>]`, a set of other classes
+    let file = include_str!("../examples/tokipona.phonet");
+    let scheme = Phonet::parse(file).expect("Failed to parse");
+
+    let words = scheme.generate(20, 3..9).expect("Failed to generate");
+    assert_eq!(words.len(), 20);
+
+    for word in words {
+        assert!(
+            word.chars().all(|ch| "ptkmnswljiueoa".contains(ch)),
+            "Generated word `{word}` contains character outside of classes"
+        );
+        assert!(
+            scheme.first_violation(&word).is_none(),
+            "Generated word `{word}` is invalid"
+        );
+    }
+}
+
+#[test]
+fn generate_stops_when_unsatisfiable() {
+    let scheme = Phonet::parse("$_ = [ptka]; @ Too long; ! .{5}").expect("Failed to parse");
+
+    let options = GenerateOptions {
+        length: 6..8,
+        max_attempts: Some(500),
+        ..Default::default()
+    };
+
+    match scheme.generate_with(3, &options) {
+        Err(Error::GenerationExhausted {
+            requested,
+            attempts,
+            words,
+            rejected_by,
+            reason,
+        }) => {
+            assert_eq!(requested, 3);
+            assert_eq!(attempts, 500);
+            assert!(words.is_empty());
+            assert_eq!(rejected_by, Some(1));
+            assert_eq!(reason.as_deref(), Some("Too long"));
+        }
+        _ => panic!("Generation should have stopped"),
+    }
+}
+
+#[test]
+fn generate_length_range_must_not_be_empty() {
+    let scheme = Phonet::parse("$_ = [ptka]").expect("Failed to parse");
+
+    let reversed = std::ops::Range { start: 20, end: 14 };
+    for (length, syllables) in [(reversed, None), (5..5, None), (3..14, Some(2..2))] {
+        let options = GenerateOptions {
+            length: length.clone(),
+            syllables: syllables.clone(),
+            ..Default::default()
+        };
+
+        let range = syllables.unwrap_or(length);
+        match scheme.generate_with(1, &options) {
+            Err(Error::EmptyLengthRange { start, end }) => {
+                assert_eq!(start..end, range);
+            }
+            _ => panic!("Empty range `{range:?}` should not generate"),
+        }
+    }
+
+    // Default maximum length is exclusive
+    let (code, _, stderr) =
+        run_cli(&["-f", "examples/tokipona.phonet", "-n", "-g", "--gmin", "20"]);
+    assert_eq!(code, 4, "{stderr}");
+    assert!(stderr.contains("`20..14` is empty"), "{stderr}");
+
+    let (code, _, stderr) = run_cli(&[
+        "-f",
+        "examples/tokipona.phonet",
+        "-n",
+        "-g",
+        "--gmin",
+        "5",
+        "--gmax",
+        "5",
+    ]);
+    assert_eq!(code, 4, "{stderr}");
+    assert!(stderr.contains("`5..5` is empty"), "{stderr}");
+}
+
+#[test]
+fn weights_of_unknown_segments_are_found() {
+    let scheme = Phonet::parse("$_ = [ptka]; $C = (?:[ptk]|sh); $X = .x; % p:2 sh:1 ch:1 zx:1 q:0")
+        .expect("Failed to parse");
+
+    // Segment of class which cannot be expanded is matched with class
+    assert_eq!(scheme.unknown_weights(), vec!["ch", "q"]);
+}
+
+#[test]
+fn generate_many_words() {
+    // Attempt limit is for each word, not for all words together
+    let file = include_str!("../examples/tokipona.phonet");
+    let scheme = Phonet::parse(file).expect("Failed to parse");
+
+    let options = GenerateOptions {
+        seed: Some(1),
+        ..Default::default()
+    };
+    let words = scheme
+        .generate_with(20_000, &options)
+        .expect("Failed to generate");
+    assert_eq!(words.len(), 20_000);
+}
+
+#[test]
+fn generate_with_seed_is_reproducible() {
+    let file = include_str!("../examples/ivalingo.phonet");
+    let scheme = Phonet::parse(file).expect("Failed to parse");
+
+    let options = GenerateOptions {
+        seed: Some(1234),
+        ..Default::default()
+    };
+
+    let first = scheme
+        .generate_with(20, &options)
+        .expect("Failed to generate");
+    let second = scheme
+        .generate_with(20, &options)
+        .expect("Failed to generate");
+    assert_eq!(first, second, "Same seed generated different words");
+
+    let other = GenerateOptions {
+        seed: Some(4321),
+        ..Default::default()
+    };
+    assert_ne!(
+        first,
+        scheme
+            .generate_with(20, &other)
+            .expect("Failed to generate"),
+        "Different seeds generated same words"
+    );
+
+    // Same seed generates same words with any version of dependencies, on any platform
+    let scheme = Phonet::parse("$_ = [ptka]; ! kk").expect("Failed to parse");
+    let options = GenerateOptions {
+        length: 3..6,
+        seed: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(
+        scheme
+            .generate_with(5, &options)
+            .expect("Failed to generate"),
+        vec!["tkpak", "aapaa", "takp", "kaaa", "aaaa"]
+    );
+}
+
+#[test]
+fn generate_uses_weights() {
+    let scheme = Phonet::parse("$_ = (?: [ptka] | sh ); % p:0 sh:0 t:10").expect("Failed to parse");
+
+    let words = scheme.generate(20, 3..6).expect("Failed to generate");
+    for word in words {
+        assert!(
+            !word.contains('p') && !word.contains("sh"),
+            "Generated word `{word}` contains segment with weight of zero"
+        );
+    }
+
+    assert!(Phonet::parse("% p").is_err(), "Weight without number");
+    assert!(Phonet::parse("% p:-1").is_err(), "Negative weight");
+    assert!(Phonet::parse("% :1").is_err(), "Weight without segment");
+    assert!(Phonet::parse("% p:1 p:2").is_err(), "Duplicate weight");
+}
+
+#[test]
+fn generate_by_syllables() {
+    let file = include_str!("../examples/ivalingo.phonet");
+    let scheme = Phonet::parse(file).expect("Failed to parse");
+
+    let options = GenerateOptions {
+        syllables: Some(2..4),
+        seed: Some(0),
+        ..Default::default()
+    };
+    let words = scheme
+        .generate_with(10, &options)
+        .expect("Failed to generate");
+    assert_eq!(words.len(), 10);
+
+    // Words should be valid
+    let mut scheme = scheme;
+    scheme.tests = words
+        .into_iter()
+        .map(|word| TestDefinition::Test { intent: true, word })
+        .collect();
+    assert_eq!(scheme.run().fail_count, 0);
+
+    assert!(
+        Phonet::parse("= <X> a").is_err(),
+        "Syllable template with unknown class should not parse"
+    );
+    assert!(
+        matches!(
+            Phonet::parse("$_ = [a]")
+                .unwrap()
+                .generate_with(1, &options),
+            Err(Error::MissingSyllables)
+        ),
+        "Generating by syllable without templates should fail"
+    );
+
+    // Templates can be made without parsing
+    let mut scheme = Phonet::parse("$_ = [pta]; $C = [pt]").expect("Failed to parse");
+    scheme.syllables = vec![Syllable {
+        parts: vec![
+            SyllablePart::Class("C".to_string()),
+            SyllablePart::Optional(Box::new(SyllablePart::Literal("a".to_string()))),
+        ],
+        line: 0,
+    }];
+    let words = scheme
+        .generate_with(10, &options)
+        .expect("Failed to generate");
+    for word in words {
+        assert!(
+            word.starts_with(['p', 't']) && !word.contains("aa"),
+            "Generated word `{word}` does not follow template"
+        );
+    }
+}
+
+#[test]
+fn enumerate_all_valid_words() {
+    let scheme = Phonet::parse("$_ = [ab]; ! aa").expect("Failed to parse");
+    assert_eq!(
+        scheme
+            .enumerate(3)
+            .expect("Failed to enumerate")
+            .collect::<Vec<_>>(),
+        vec!["a", "ab", "aba", "abb", "b", "ba", "bab", "bb", "bba", "bbb"]
+    );
+
+    // Overlapping segments should not create duplicates
+    let scheme = Phonet::parse("$_ = (?: a | b | ab )").expect("Failed to parse");
+    assert_eq!(
+        scheme
+            .enumerate(2)
+            .expect("Failed to enumerate")
+            .collect::<Vec<_>>(),
+        vec!["a", "aa", "ab", "b", "ba", "bb"]
+    );
+
+    // Words must be made of whole segments, and follow end-anchored rules
+    let scheme = Phonet::parse("$_ = (?: a | sh ); ! a$").expect("Failed to parse");
+    assert_eq!(
+        scheme
+            .enumerate(3)
+            .expect("Failed to enumerate")
+            .collect::<Vec<_>>(),
+        vec!["ash", "sh"]
+    );
+}
+
+#[test]
+fn capacity_counts_valid_words() {
+    let scheme = Phonet::parse("$_ = [ab]; ! aa").expect("Failed to parse");
+
+    let capacities = scheme
+        .capacity(1..4, &CapacityOptions::default())
+        .expect("Failed to count");
+    let counts: Vec<_> = capacities
+        .iter()
+        .map(|capacity| match capacity.count {
+            CapacityCount::Exact(count) => count,
+            _ => panic!("Small length should be counted exactly"),
+        })
+        .collect();
+    assert_eq!(counts, vec![2, 3, 5]);
+
+    // Estimate should contain real count (half of all words end in `a`)
+    let scheme = Phonet::parse("$_ = [ab]; ! a$").expect("Failed to parse");
+    let options = CapacityOptions {
+        exact_limit: 0.0,
+        seed: Some(0),
+        ..Default::default()
+    };
+    let capacities = scheme.capacity(10..11, &options).expect("Failed to count");
+    match capacities[0].count {
+        CapacityCount::Estimate { low, high, .. } => {
+            assert_eq!(capacities[0].candidates, 1024.0);
+            assert!(low <= 512.0 && 512.0 <= high);
+        }
+        _ => panic!("Length should be estimated"),
+    }
+
+    // Words are counted once, even if segments overlap
+    let scheme = Phonet::parse("$_ = (?: s | h | sh | a )").expect("Failed to parse");
+    let exact = scheme
+        .capacity(6..7, &CapacityOptions::default())
+        .expect("Failed to count");
+    assert!(matches!(exact[0].count, CapacityCount::Exact(729)));
+
+    let capacities = scheme.capacity(6..7, &options).expect("Failed to count");
+    match capacities[0].count {
+        CapacityCount::Estimate {
+            count, low, high, ..
+        } => {
+            assert!(capacities[0].candidates > 729.0);
+            assert!(low <= 729.0 && 729.0 <= high);
+            assert!((count - 729.0).abs() < 729.0 * 0.1);
+        }
+        _ => panic!("Length should be estimated"),
+    }
+}
+
+#[test]
+fn all_violations_are_reported() {
+    let scheme =
+        Phonet::parse("@ One; ! a; + ^b; @ Three; ! c; ?+ ac bd").expect("Failed to parse");
+
+    assert_eq!(
+        scheme.violations("ac"),
+        vec![
+            Violation {
+                intent: false,
+                line: 1,
+                file: None,
+                reason: Some("One".to_string()),
+                span: Some(0..1),
+            },
+            Violation {
+                intent: true,
+                line: 1,
+                file: None,
+                reason: Some("One".to_string()),
+                span: None,
+            },
+            Violation {
+                intent: false,
+                line: 1,
+                file: None,
+                reason: Some("Three".to_string()),
+                span: Some(1..2),
+            },
+        ]
+    );
+    assert!(scheme.violations("bd").is_empty());
+
+    // Test fails with first violation
+    assert_eq!(
+        scheme.first_violation("ac"),
+        scheme.violations("ac").into_iter().next()
+    );
+    assert_eq!(scheme.first_violation("bd"), None);
+
+    let results = scheme.run_with(&RunOptions {
+        all_violations: true,
+        ..Default::default()
+    });
+    assert_eq!(results.fail_count, 1);
+    assert!(matches!(
+        &results.list[0],
+        TestResult::Detailed { pass: false, violations, .. } if violations.len() == 3
+    ));
+}
+
+#[test]
+fn negative_rule_span_is_given() {
+    let scheme =
+        Phonet::parse("$V = [aeiou]; ! <V>{3}; + ^[a-z]+$; ?+ beauty 9").expect("Failed to parse");
+
+    let results = scheme.run();
+    assert!(matches!(
+        &results.list[0],
+        TestResult::Test { pass: false, span: Some(span), .. } if *span == (1..4)
+    ));
+    // Positive rules have no span
+    assert!(matches!(
+        &results.list[1],
+        TestResult::Test {
+            pass: false,
+            span: None,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn rule_coverage_is_recorded() {
+    let scheme = Phonet::parse("! a; ! b; ! c; ?! a ab; ?+ d; ?+ b").expect("Failed to parse");
+
+    let coverage = scheme
+        .run_with(&RunOptions {
+            coverage: true,
+            ..Default::default()
+        })
+        .coverage
+        .expect("Coverage should be recorded");
+
+    let counts: Vec<_> = coverage
+        .rules
+        .iter()
+        .map(|rule| (rule.rejected, rule.rejected_negative, rule.deciding))
+        .collect();
+    assert_eq!(counts, vec![(2, 2, 2), (2, 1, 1), (0, 0, 0)]);
+
+    let uncovered: Vec<_> = coverage.uncovered().map(|rule| rule.line).collect();
+    assert_eq!(uncovered, vec![1]);
+    assert_eq!(coverage.never_rejecting().count(), 1);
+
+    assert!(scheme.run().coverage.is_none());
+
+    // Report has a row for each rule, and lists rules which never reject any test separately
+    let path = temp_files(
+        "coverage",
+        &[(
+            "main.phonet",
+            "$_ = [ptka]\n@ Never used\n! x\n@ Only positive\n! p$\n@ Covered\n! kk\n?+ tap taka\n?! takk",
+        )],
+    )
+    .join("main.phonet");
+    let (code, stdout, _) = run_cli(&["-f", path.to_str().unwrap(), "-n", "--coverage"]);
+    assert_eq!(code, 1, "{stdout}");
+    let report = stdout
+        .split_once("Rule coverage:")
+        .expect("Coverage was not displayed")
+        .1;
+    assert_eq!(
+        report.lines().collect::<Vec<_>>(),
+        vec![
+            " 1 of 3 rules have a negative test",
+            "Line  Rejected  Deciding  Reason",
+            "   3         0         0  Never used",
+            "   5         1         1  Only positive",
+            "   7         1         1  Covered",
+            "Rules which never reject any test:",
+            " - line 3: Never used",
+            "Rules without a negative test:",
+            " - line 5: Only positive",
+        ]
+    );
+}
+
+#[test]
+fn results_format_as_machine_readable() {
+    let file = include_str!("../examples/example.phonet");
+    let results = Phonet::parse(file).expect("Failed to parse").run();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&results.to_json()).expect("JSON should be valid");
+    assert_eq!(json["fail_count"], 2);
+    assert_eq!(json["list"][1]["test"]["word"], "taso");
+
+    let tap = results.to_tap();
+    assert!(tap.starts_with("TAP version 13\n1..20\n"));
+    assert_eq!(tap.matches("\nnot ok ").count(), 2);
+
+    let junit = results.to_junit("example.phonet");
+    assert!(junit.contains(r#"<testsuite name="example.phonet" tests="20" failures="2">"#));
+    assert_eq!(junit.matches("<failure ").count(), 2);
+
+    assert!(results.format(OutputFormat::Human, "").is_none());
+}
+
+#[test]
+fn exit_status_gives_first_failure() {
+    let dir = temp_files(
+        "exit-status",
+        &[
+            ("pass.phonet", "$_ = [ptka]\n! kk\n?+ taka"),
+            ("fail.phonet", "$_ = [ptka]\n! kk\n?+ takka"),
+            ("empty.phonet", "$_ = [ptka]\n! kk"),
+            ("invalid.phonet", "+ ("),
+            ("unbounded.phonet", "$_ = [ptka]+\n?+ taka"),
+            ("unbounded-fail.phonet", "$_ = [ptka]+\n! kk\n?+ takka"),
+        ],
+    );
+    let status = |args: &[&str]| {
+        let path = dir.join(args[0]);
+        let mut full = vec!["-f", path.to_str().unwrap(), "-n"];
+        full.extend(&args[1..]);
+        run_cli(&full).0
+    };
+
+    assert_eq!(status(&["pass.phonet"]), 0);
+    assert_eq!(status(&["fail.phonet"]), 1);
+    assert_eq!(status(&["invalid.phonet"]), 2);
+    assert_eq!(status(&["missing.phonet"]), 3);
+
+    // No tests is only a failure if asked
+    assert_eq!(status(&["empty.phonet"]), 0);
+    assert_eq!(status(&["empty.phonet", "--fail-on-empty"]), 1);
+
+    // Generation failure does not replace earlier failure
+    assert_eq!(status(&["unbounded.phonet", "-e", "2"]), 4);
+    assert_eq!(status(&["unbounded.phonet", "--capacity", "1..3"]), 4);
+    assert_eq!(
+        status(&["fail.phonet", "-g", "--gmin", "5", "--gmax", "5"]),
+        1
+    );
+    assert_eq!(status(&["unbounded-fail.phonet", "-e", "2"]), 1);
+    assert_eq!(status(&["unbounded-fail.phonet", "--capacity", "1..3"]), 1);
+}
+
+#[test]
+fn machine_readable_output_is_kept_separate() {
+    let args = [
+        "-f",
+        "examples/example.phonet",
+        "--format",
+        "json",
+        "-g",
+        "3",
+        "-e",
+        "2",
+        "--capacity",
+        "1..3",
+    ];
+    let (_, stdout, stderr) = run_cli(&args);
+
+    // Standard output only has results
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Output is not valid JSON");
+    assert_eq!(json["fail_count"], 2);
+    assert!(stdout.ends_with('\n'));
+
+    // Words are written to standard error
+    assert!(stderr.contains("Randomly generated words"), "{stderr}");
+    assert!(stderr.contains("Valid words, up to length 2"), "{stderr}");
+    assert!(stderr.contains("Candidates"), "{stderr}");
+
+    let (_, stdout, _) = run_cli(&["-f", "examples/example.phonet", "--format", "tap", "-g"]);
+    assert!(stdout.starts_with("TAP version 13\n"));
+    assert!(stdout.ends_with('\n') && !stdout.contains("Randomly generated"));
+}
+
+#[test]
+fn format_is_rejected_with_subcommand() {
+    for command in ["lint", "fmt", "words"] {
+        let args = ["-f", "examples/example.phonet", "--format", "json", command];
+        let (code, stdout, stderr) = run_cli(&args);
+
+        assert_eq!(code, 2, "{stderr}");
+        assert!(stdout.is_empty(), "{stdout}");
+        assert!(stderr.contains("`--format` cannot be used"), "{stderr}");
+    }
+}
+
+#[test]
+fn results_of_files_format_together() {
+    let example = Phonet::parse(include_str!("../examples/example.phonet"))
+        .expect("Failed to parse")
+        .run();
+    let tokipona = Phonet::parse(include_str!("../examples/tokipona.phonet"))
+        .expect("Failed to parse")
+        .run();
+    let files = [("example.phonet", &example), ("tokipona.phonet", &tokipona)];
+    let tests = example.test_count() + tokipona.test_count();
+
+    let json = format_files(&files, OutputFormat::Json).expect("JSON should be given");
+    let json: serde_json::Value = serde_json::from_str(&json).expect("JSON should be valid");
+    assert_eq!(json[0]["file"], "example.phonet");
+    assert_eq!(json[0]["fail_count"], 2);
+    assert_eq!(json[1]["file"], "tokipona.phonet");
+
+    // Tests are numbered across files
+    let tap = format_files(&files, OutputFormat::Tap).expect("TAP should be given");
+    assert!(tap.starts_with(&format!(
+        "TAP version 13\n1..{tests}\n# File example.phonet\n"
+    )));
+    assert!(tap.contains(&format!("\nok {tests} - ")));
+    assert_eq!(tap.matches("\nnot ok ").count(), 2);
+
+    let junit = format_files(&files, OutputFormat::Junit).expect("JUnit should be given");
+    assert!(junit.contains(&format!(r#"<testsuites tests="{tests}" failures="2">"#)));
+    assert_eq!(junit.matches("<testsuite name=").count(), 2);
+
+    assert!(format_files(&files, OutputFormat::Human).is_none());
+}
+
+#[test]
+fn every_parse_error_is_collected() {
+    let file = "
+        $C = [ptk]
+        $V = [aeiou]
+        $Bad = <Missing>
+        x
+        + ^ <C> <V> $
+        ! <Bad>
+        ! (
+        ~ <>
+        ~ //
+        ?+ pa
+        ?! a
+    ";
+
+    let (scheme, errors) = Phonet::parse_recover(file);
+
+    assert_eq!(errors.len(), 4);
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|err| err.span().map(|span| span.line))
+        .collect();
+    assert_eq!(lines, vec![Some(5), Some(10), Some(4), Some(8)]);
+    assert!(matches!(
+        errors[0],
+        Error::UnknownLineOperator { ch: 'x', .. }
+    ));
+    assert!(matches!(errors[1], Error::ModeAlreadyDefined { .. }));
+    assert!(matches!(&errors[2], Error::ClassNotFound { name, .. } if name == "Missing"));
+    assert!(matches!(errors[3], Error::RegexFail { .. }));
+
+    // Rules and tests without errors are kept
+    assert_eq!(scheme.rules.len(), 1);
+    assert_eq!(scheme.test_count(), 2);
+    assert_eq!(scheme.run().fail_count, 0);
+
+    // First error is returned
+    assert!(matches!(
+        Phonet::parse(file),
+        Err(Error::UnknownLineOperator {
+            span: Span { line: 5, .. },
+            ..
+        })
+    ));
+}
+
+#[test]
+fn errors_have_position_and_hint() {
+    let file = "$Cons = [ptk]\n$V = [aeiou]\n+ ^ <V> &\n  <cons> $";
+
+    let err = Phonet::parse(file).expect_err("Class should not be found");
+    assert_eq!(
+        err.span(),
+        Some(Span {
+            line: 4,
+            column: 3,
+            len: 6
+        })
+    );
+    assert_eq!(err.hint().as_deref(), Some("did you mean `$Cons`?"));
+
+    assert_eq!(
+        err.render(file, true),
+        [
+            "error: Class not found, with name `cons`, at line 4",
+            "  |",
+            "4 |   <cons> $",
+            "  |   ^^^^^^ did you mean `$Cons`?",
+            "",
+        ]
+        .join("\n")
+    );
+
+    let err = Phonet::parse("$C = p\n?+ pa\n? pa").expect_err("Intent should be invalid");
+    assert_eq!(
+        err.span(),
+        Some(Span {
+            line: 3,
+            column: 3,
+            len: 1
+        })
+    );
+}
+
+#[test]
+fn class_errors_are_shown_in_definition() {
+    let file = "
+        $A = <B> x
+        $B = [ab] <C>
+        $C = <Missing>
+        + <A>
+        + <C>
+    ";
+
+    let (scheme, errors) = Phonet::parse_recover(file);
+
+    // Error is only shown once, in definition of class it is in
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], Error::ClassNotFound { name, .. } if name == "Missing"));
+    assert_eq!(
+        errors[0].span(),
+        Some(Span {
+            line: 4,
+            column: 14,
+            len: 9
+        })
+    );
+
+    // Classes which use class with error are left out
+    assert!(scheme.classes.is_empty());
+    assert!(scheme.rules.is_empty());
+
+    // Error found through several classes, in any order of definition
+    let err = Phonet::parse("+ <A>\n$A = <B>\n$C = <D>\n$B = <C>")
+        .expect_err("Class should not be found");
+    assert!(matches!(&err, Error::ClassNotFound { name, .. } if name == "D"));
+    assert_eq!(err.span().map(|span| span.line), Some(3));
+}
+
+#[test]
+fn circular_classes_are_detected() {
+    let file = "
+        $A = <B>
+        $B = x <A>
+        $C = <C>
+        $D = [ptk]
+        + <A> <D>
+    ";
+
+    let (scheme, errors) = Phonet::parse_recover(file);
+
+    let cycles: Vec<_> = errors
+        .iter()
+        .map(|err| match err {
+            Error::CircularClass { path, lines, .. } => (path.join(" -> "), lines.clone()),
+            _ => panic!("Error should be circular class reference: {err}"),
+        })
+        .collect();
+    assert_eq!(
+        cycles,
+        vec![
+            ("A -> B -> A".to_string(), vec![2, 3]),
+            ("C -> C".to_string(), vec![4]),
+        ]
+    );
+
+    // Position of class name which completes cycle
+    assert_eq!(
+        errors[0].span(),
+        Some(Span {
+            line: 3,
+            column: 16,
+            len: 3
+        })
+    );
+
+    // Other classes are kept
+    assert!(scheme.classes.contains_key("D"));
+    assert_eq!(scheme.classes.len(), 1);
+}
+
+#[test]
+fn lint_finds_possible_mistakes() {
+    let file = "
+        $_ = [ptkaio]
+        $C = [ptk]
+        $V = [aioy]
+        $Unused = [xyz]
+        % p:2 sh:1 x:1
+        @ Unused reason
+        @ Invalid letters
+        + ^ <_>+ $
+        ! <C> <C>
+        !<C><C>
+        ?+ pata pata
+        ?! pata
+    ";
+    let scheme = Phonet::parse(file).expect("Failed to parse");
+
+    let warnings: Vec<_> = scheme
+        .lint(file)
+        .into_iter()
+        .map(|warning| (warning.lint, warning.span.line))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (Lint::UnusedClass, 4),
+            (Lint::UncoveredSegment, 4),
+            (Lint::UnusedClass, 5),
+            (Lint::UncoveredSegment, 5),
+            (Lint::UnknownWeight, 6),
+            (Lint::UnusedReason, 7),
+            (Lint::DuplicateRule, 11),
+            (Lint::DuplicateTest, 12),
+            (Lint::ContradictoryTest, 13),
+        ]
+    );
+
+    // Warnings can be allowed in file
+    let file = format!("#! allow W001, uncovered-segment\n{file}");
+    let scheme = Phonet::parse(&file).expect("Failed to parse");
+    let warnings = scheme.lint(&file);
+    assert_eq!(warnings.len(), 5);
+    assert!(warnings
+        .iter()
+        .all(|warning| !matches!(warning.lint, Lint::UnusedClass | Lint::UncoveredSegment)));
+}
+
+#[test]
+fn formatting_keeps_scheme() {
+    let files = [
+        include_str!("../examples/example.phonet"),
+        include_str!("../examples/esperanto.phonet"),
+        include_str!("../examples/ivalingo.phonet"),
+        include_str!("../examples/tokipona.phonet"),
+    ];
+
+    for file in files {
+        let formatted = format_file(file);
+        assert_eq!(
+            format_file(&formatted),
+            formatted,
+            "Formatting is not idempotent"
+        );
+
+        let scheme = Phonet::parse(file).expect("Failed to parse");
+        let formatted = Phonet::parse(&formatted).expect("Failed to parse formatted file");
+
+        let rules = |scheme: &Phonet| -> Vec<_> {
+            scheme
+                .rules
+                .iter()
+                .map(|rule| (rule.intent, rule.pattern.to_string(), rule.reason_ref))
+                .collect()
+        };
+        assert_eq!(rules(&formatted), rules(&scheme));
+        assert_eq!(formatted.classes, scheme.classes);
+        assert_eq!(formatted.reasons, scheme.reasons);
+        assert_eq!(
+            format!("{:?}", formatted.tests),
+            format!("{:?}", scheme.tests)
+        );
+    }
+}
+
+#[test]
+fn syntax_tree_prints_file_exactly() {
+    let files = [
+        include_str!("../examples/example.phonet"),
+        include_str!("../examples/esperanto.phonet"),
+        include_str!("../examples/ivalingo.phonet"),
+        include_str!("../examples/test.phonet"),
+        include_str!("../examples/tokipona.phonet"),
+    ];
+
+    for file in files {
+        assert_eq!(SyntaxTree::parse(file).to_string(), file);
+    }
+
+    // Change tree, and parse changed file
+    let file = include_str!("../examples/example.phonet");
+    let mut tree = SyntaxTree::parse(file);
+
+    let comments = tree
+        .nodes
+        .iter()
+        .filter(|node| node.kind() == Kind::Comment)
+        .count();
+    assert_eq!(comments, 14);
+
+    // Remove failing tests, and their note
+    let note = tree
+        .nodes
+        .iter()
+        .position(|node| node.raw == "* Examples of failing tests")
+        .expect("Note not found");
+    tree.nodes.drain(note..note + 5);
+    assert_eq!(tree.nodes[note].kind(), Kind::Blank);
+
+    // Add passing test to start of file
+    tree.nodes.insert(0, Node::new("?+ silo", End::Linebreak));
+
+    let scheme = Phonet::parse(&tree.to_string()).expect("Failed to parse");
+    assert_eq!(scheme.run().fail_count, 0);
+    assert_eq!(scheme.test_count(), 19);
+}
+
+#[test]
+fn minified_file_has_same_results() {
+    let file = "
+        ~<>
+        $_ = [abc]
+        @* Letters
+        + ^ ⟨_⟩+ $
+            ?+ ab ba
+        * Note
+            ?! d
+    ";
+    let scheme = Phonet::parse(file).expect("Failed to parse");
+    assert_eq!(
+        scheme.minify(true),
+        "~<>;$_=[abc];@*Letters;+^<_>+$;?+ab ba;*Note;?!d"
+    );
+    assert_eq!(scheme.minify(false), "~<>;$_=[abc];@Letters;+^<_>+$");
+
+    let files = [
+        include_str!("../examples/example.phonet"),
+        include_str!("../examples/esperanto.phonet"),
+        include_str!("../examples/ivalingo.phonet"),
+        include_str!("../examples/tokipona.phonet"),
+    ];
+
+    // Reason of each rule
+    let reasons = |scheme: &Phonet| -> Vec<_> {
+        scheme
+            .rules
+            .iter()
+            .map(|rule| rule.reason_ref.map(|reason| scheme.reasons[reason].clone()))
+            .collect()
+    };
+
+    for file in files {
+        let scheme = Phonet::parse(file).expect("Failed to parse");
+
+        let minified = Phonet::parse(&scheme.minify(true)).expect("Failed to parse minified file");
+        assert_eq!(
+            minified.run().format(OutputFormat::Json, "phonet"),
+            scheme.run().format(OutputFormat::Json, "phonet")
+        );
+        assert_eq!(reasons(&minified), reasons(&scheme));
+
+        let minified = Phonet::parse(&scheme.minify(false)).expect("Failed to parse minified file");
+        assert!(minified.tests.is_empty());
+        assert_eq!(reasons(&minified), reasons(&scheme));
+    }
+}
+
+/// Write files to a new temporary directory, and get path of directory
+fn temp_files(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("phonet-test-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    for (path, text) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("Failed to create directory");
+        std::fs::write(path, text).expect("Failed to write file");
+    }
+    dir
+}
+
+#[test]
+fn included_definitions_can_be_overridden() {
+    let dir = temp_files(
+        "include",
+        &[
+            (
+                "common.phonet",
+                "~<>\n$_ = ⟨C⟩ | ⟨V⟩\n$C = [ptkl]\n$V = [aeiou]\n@ Syllables\n+ ^ (⟨C⟩⟨V⟩)+ $\n?+ should_not_run",
+            ),
+            (
+                "dialects/north.phonet",
+                "< ../common.phonet\n< ../common.phonet\n$C = [ptk]\n?+ tapo\n?! lapo",
+            ),
+        ],
+    );
+
+    let scheme = Phonet::parse_file(dir.join("dialects/north.phonet")).expect("Failed to parse");
+
+    // Tests of included file are left out
+    assert_eq!(scheme.test_count(), 2);
+    assert_eq!(scheme.run().fail_count, 0);
+
+    // Included rule uses overridden class
+    assert_eq!(scheme.rules.len(), 1);
+    assert_eq!(scheme.reasons, vec!["Syllables"]);
+    assert_eq!(scheme.classes["C"], "(?:[ptk])");
+
+    // Include statement is kept when minifying
+    assert!(scheme
+        .minify(false)
+        .starts_with("<../common.phonet;<../common.phonet;$C="));
+
+    // Class defined in file cannot be defined again
+    let file = "< ../common.phonet\n$C = [ptk]\n$C = [p]";
+    let (_, errors) = Phonet::parse_recover_at(file, dir.join("dialects/south.phonet"));
+    assert!(matches!(
+        errors.as_slice(),
+        [Error::ClassAlreadyExist { name, .. }] if name == "C"
+    ));
+}
+
+#[test]
+fn included_rules_keep_file() {
+    let dir = temp_files(
+        "include-location",
+        &[
+            ("common.phonet", "$_ = [ptkla]\n\n@ No l\n! l"),
+            ("main.phonet", "< common.phonet\n@ No pp\n! pp\n?+ lapo ppa"),
+        ],
+    );
+    let path = dir.join("main.phonet");
+    let scheme = Phonet::parse_file(&path).expect("Failed to parse");
+
+    // Rule of included file has path of file, rule of this file has none
+    let violation = scheme
+        .first_violation("la")
+        .expect("Word should be invalid");
+    assert_eq!(violation.line, 4);
+    let file = violation.file.as_deref().expect("Rule should have file");
+    assert!(file.ends_with("common.phonet"), "{file}");
+    assert_eq!(violation.location(), format!("line 4 of `{file}`"));
+
+    let violation = scheme
+        .first_violation("ppa")
+        .expect("Word should be invalid");
+    assert_eq!((violation.line, violation.file.as_deref()), (3, None));
+    assert_eq!(violation.location(), "line 3");
+
+    // Location is displayed with file
+    let (_, stdout, _) = run_cli(&["-f", path.to_str().unwrap(), "-n", "-a", "--coverage"]);
+    assert!(
+        stdout.contains(&format!("No l (line 4 of `{file}`)")),
+        "{stdout}"
+    );
+    assert!(stdout.contains("No pp (line 3)"), "{stdout}");
+    assert!(
+        stdout.contains(&format!("   4         1         1  No l (in `{file}`)")),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn include_errors_are_shown_in_included_file() {
+    let dir = temp_files(
+        "include-errors",
+        &[
+            (
+                "main.phonet",
+                "$_ = [ab]\n\n< lib/broken.phonet\n< missing.phonet",
+            ),
+            ("lib/broken.phonet", "$V = [aeiou]\n+ <C>"),
+            ("a.phonet", "< b.phonet"),
+            ("b.phonet", "$_ = [ab]\n< a.phonet"),
+        ],
+    );
+
+    let path = dir.join("main.phonet");
+    let file = std::fs::read_to_string(&path).unwrap();
+    let (_, errors) = Phonet::parse_recover_at(&file, &path);
+    assert_eq!(errors.len(), 2);
+
+    // Error in included file has position in that file, and of include statement
+    let Error::InFile { source, span, .. } = &errors[0] else {
+        panic!("Error should be in included file: {}", errors[0]);
+    };
+    assert!(matches!(**source, Error::ClassNotFound { .. }));
+    assert_eq!(source.span().map(|span| span.line), Some(2));
+    assert_eq!(span.line, 3);
+    assert!(errors[0]
+        .to_string()
+        .contains("broken.phonet` included at line 3"));
+    assert!(errors[0].render(&file, true).contains("2 | + <C>"));
+
+    assert!(matches!(
+        &errors[1],
+        Error::IncludeFail { span, .. } if span.line == 4
+    ));
+
+    // Circular include, found in included file
+    let Err(Error::InFile { source, span, .. }) = Phonet::parse_file(dir.join("a.phonet")) else {
+        panic!("Include should be circular");
+    };
+    assert_eq!(span.line, 1);
+    let Error::CircularInclude { path, span } = *source else {
+        panic!("Error should be circular include: {source}");
+    };
+    assert_eq!(span.line, 2);
+    assert_eq!(path.len(), 3);
+    assert!(path[0].ends_with("a.phonet") && path[2].ends_with("a.phonet"));
+
+    assert!(matches!(
+        Phonet::parse_file(dir.join("none.phonet")),
+        Err(Error::ReadFail { .. })
+    ));
+}
+
+#[test]
+fn combined_rules_give_same_results() {
+    let files = [
+        include_str!("../examples/example.phonet"),
+        include_str!("../examples/esperanto.phonet"),
+        include_str!("../examples/ivalingo.phonet"),
+        include_str!("../examples/tokipona.phonet"),
+    ];
+
+    for file in files {
+        let mut scheme = Phonet::parse(file).expect("Failed to parse");
+        assert!(scheme.combined_rule_count() > 0);
+
+        // Words of tests, and every valid word
+        let mut words: Vec<String> = scheme
+            .tests
+            .iter()
+            .filter_map(|test| match test {
+                TestDefinition::Test { word, .. } => Some(word.clone()),
+                TestDefinition::Note(_) => None,
+            })
+            .collect();
+        if let Ok(valid) = scheme.enumerate(3) {
+            words.extend(valid);
+        }
+
+        // First rule that word does not follow, checking each rule in order
+        let first_failing = |scheme: &Phonet, word: &str| {
+            scheme
+                .rules
+                .iter()
+                .position(|rule| rule.intent != rule.pattern.is_match(word).unwrap())
+                .map(|index| scheme.rules[index].line)
+        };
+
+        for word in &words {
+            let violation = scheme.first_violation(word);
+            assert_eq!(
+                violation.map(|violation| violation.line),
+                first_failing(&scheme, word),
+                "{word}"
+            );
+        }
+
+        // Changed rules are used after compiling again
+        scheme.rules.reverse();
+        scheme.compile_rules();
+        for word in &words {
+            assert_eq!(
+                scheme.first_violation(word).map(|violation| violation.line),
+                first_failing(&scheme, word),
+                "{word}"
+            );
+        }
+    }
+}
+
+/// Run binary with arguments, in root of package
+///
+/// Returns exit code, standard output, and standard error
+fn run_cli(args: &[&str]) -> (i32, String, String) {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_phonet"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to run binary");
+
+    (
+        output.status.code().expect("Binary was stopped by signal"),
+        String::from_utf8(output.stdout).expect("Output is not UTF-8"),
+        String::from_utf8(output.stderr).expect("Output is not UTF-8"),
+    )
+}